@@ -0,0 +1,594 @@
+//!
+//! Code generation: the component's struct, its `Default` impl, and an `impl`
+//! with its script functions (and a constructor, if it has state).
+//!
+//! ```rust,ignore
+//! // fruit_basket.pony
+//! #[derive(Default)]
+//! extern {
+//!     let mut basket: HashMap<String, usize> = Default::default();
+//! }
+//!
+//! let mut added: usize = 0;
+//!
+//! pub fn add(fruit: impl ToString) { ... }
+//! ```
+//! becomes
+//! ```rust,ignore
+//! pub struct FruitBasket {
+//!     pub basket: HashMap<String, usize>,
+//!     added: usize,
+//! }
+//!
+//! impl Default for FruitBasket { ... }
+//!
+//! impl FruitBasket {
+//!     pub fn new() -> Self { ... }
+//!     pub fn add(&mut self, fruit: impl ToString) { ... }
+//! }
+//! ```
+//!
+
+use std::fmt::Debug;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{punctuated::Punctuated, Token};
+
+use crate::syntax::diagnostics::{Code, Diagnostic, Result};
+use crate::syntax::document::Document;
+use crate::syntax::script::{ScriptBlock, ScriptItem};
+
+use super::methods::{classify, Classified, FnKind};
+use super::mutability;
+use super::props::{props, PropDecl};
+use super::scope::{Rewrite, Rewriter};
+use super::state::{state, StateDecl};
+
+pub struct Component {
+    pub name: syn::Ident,
+    pub props: Vec<PropDecl>,
+    ///
+    /// Private fields, which only the component itself can set.
+    ///
+    pub state: Vec<StateDecl>,
+    pub fns: Classified,
+    ///
+    /// Every other item in `<script>`, kept as is.
+    ///
+    pub items: Vec<syn::Item>,
+}
+
+impl Component {
+    pub fn new(name: syn::Ident, document: &Document) -> Result<Self> {
+        let empty = ScriptBlock {
+            attrs: vec![],
+            items: vec![],
+        };
+        let script = document.script.as_ref().map_or(&empty, |script| &script.block);
+
+        let props = props(script)?;
+        let state = state(script, &props)?;
+        let fields: Vec<_> = props.iter().map(|prop| &prop.name).chain(state.iter().map(|state| &state.name)).cloned().collect();
+        let fns = classify(script, &fields)?;
+        mutability::check(&props, &state, &fns, &document.children)?;
+
+        let items = script
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ScriptItem::Item(item) if !matches!(**item, syn::Item::Fn(_)) => Some((**item).clone()),
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            name,
+            props,
+            state,
+            fns,
+            items,
+        })
+    }
+
+    ///
+    /// Names of the struct's fields.
+    ///
+    pub fn fields(&self) -> Vec<syn::Ident> {
+        let props = self.props.iter().map(|prop| &prop.name);
+        props.chain(self.state.iter().map(|state| &state.name)).cloned().collect()
+    }
+
+    ///
+    /// The body of `new()` or `default()`, given each prop's value,
+    /// or `None` for one already bound (as a parameter).
+    ///
+    /// With state, every field is bound to a local first, in order of declaration,
+    /// so state can be initialized from props (`let doubled: u32 = step * 2;`).
+    ///
+    fn init(&self, props: Vec<(&PropDecl, Option<TokenStream>)>) -> TokenStream {
+        if self.state.is_empty() {
+            let fields = props.iter().map(|(prop, value)| {
+                let name = &prop.name;
+                match value {
+                    Some(value) => quote!(#name: #value),
+                    None => quote!(#name),
+                }
+            });
+
+            return quote!(Self { #(#fields,)* });
+        }
+
+        let props = props.iter().filter_map(|(prop, value)| {
+            let (name, ty) = (&prop.name, &prop.ty);
+            let value = value.as_ref()?;
+
+            Some(quote!(let #name: #ty = #value;))
+        });
+
+        let state = self.state.iter().map(|state| {
+            let (name, ty) = (&state.name, &state.ty);
+            let init = match state.init {
+                Some(ref init) => init.to_token_stream(),
+                None => quote!(::std::default::Default::default()),
+            };
+
+            quote!(let #name: #ty = #init;)
+        });
+
+        let fields = self.fields();
+
+        quote! {
+            #(#props)*
+            #(#state)*
+            Self { #(#fields,)* }
+        }
+    }
+
+    ///
+    /// `pub fn new(...) -> Self`, taking each prop without a default,
+    /// for when state keeps the struct from being built by hand.
+    ///
+    fn constructor(&self) -> Option<TokenStream> {
+        if self.state.is_empty() {
+            return None;
+        }
+
+        let required = self.props.iter().filter(|prop| prop.default.is_none());
+        let args = required.map(|prop| {
+            let (name, ty) = (&prop.name, &prop.ty);
+            quote!(#name: #ty)
+        });
+
+        let props = self.props.iter().map(|prop| (prop, prop.default.as_ref().map(ToTokens::to_token_stream)));
+        let body = self.init(props.collect());
+
+        Some(quote! {
+            pub fn new(#(#args),*) -> Self {
+                #body
+            }
+        })
+    }
+
+    ///
+    /// Docs and attributes from every `extern { ... }` block, for the struct,
+    /// and whether `Default` was derived.
+    ///
+    /// A derived `Default` is left out, as it is implemented from the props' defaults instead.
+    ///
+    fn struct_attrs(&self) -> (Vec<String>, Vec<syn::Attribute>, bool) {
+        let mut docs = vec![];
+        let mut attrs = vec![];
+        let mut default = false;
+        let mut seen = vec![];
+
+        for group in self.props.iter().filter_map(|prop| prop.group.as_ref()) {
+            if seen.contains(&group.index) {
+                continue;
+            }
+
+            seen.push(group.index);
+            docs.extend(group.docs.clone());
+
+            for attr in &group.attrs {
+                let derives = match attr.path().is_ident("derive") {
+                    true => attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated).ok(),
+                    false => None,
+                };
+
+                let Some(derives) = derives else {
+                    attrs.push(attr.clone());
+                    continue;
+                };
+
+                let kept: Punctuated<syn::Path, Token![,]> = derives
+                    .into_iter()
+                    .filter(|path| {
+                        let is_default = path.segments.last().is_some_and(|s| s.ident == "Default");
+                        default |= is_default;
+                        !is_default
+                    })
+                    .collect();
+
+                if !kept.is_empty() {
+                    attrs.push(syn::parse_quote!(#[derive(#kept)]));
+                }
+            }
+        }
+
+        (docs, attrs, default)
+    }
+
+    ///
+    /// The script functions as they go in the `impl`: methods taking `&mut self`,
+    /// with fields and calls rewritten to go through `self` or `Self`.
+    ///
+    pub fn methods(&self) -> Vec<syn::ItemFn> {
+        let fields = self.fields();
+        let methods: Vec<_> = self.fns.methods().map(|f| f.name().clone()).collect();
+        let associated: Vec<_> = self.fns.associated().map(|f| f.name().clone()).collect();
+
+        self.fns
+            .fns
+            .iter()
+            .map(|f| {
+                let mut item = f.item.clone();
+                let rewrite = Rewrite {
+                    methods: &methods,
+                    associated: &associated,
+                };
+
+                Rewriter::new(&fields, rewrite).visit_fn(&mut item);
+
+                if f.kind == FnKind::Method {
+                    item.sig.inputs.insert(0, syn::parse_quote!(&mut self));
+                }
+
+                item
+            })
+            .collect()
+    }
+}
+
+impl Debug for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Component")
+            .field("name", &self.name.to_string())
+            .field("props", &self.props)
+            .field("state", &self.state)
+            .field("fns", &self.fns.fns)
+            .finish()
+    }
+}
+
+///
+/// `#[doc = "..."]` for each line of `docs`, as `///` would give.
+///
+fn doc_attrs(docs: &str) -> Vec<TokenStream> {
+    docs.lines()
+        .map(|line| {
+            let line = format!(" {line}");
+            quote!(#[doc = #line])
+        })
+        .collect()
+}
+
+impl ToTokens for Component {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name;
+        let items = &self.items;
+
+        let (docs, attrs, derives_default) = self.struct_attrs();
+        let docs = doc_attrs(&docs.join("\n"));
+
+        let fields = self.props.iter().map(|prop| {
+            let docs = prop.docs.as_deref().map(doc_attrs).unwrap_or_default();
+            let attrs = &prop.attrs;
+            let (name, ty) = (&prop.name, &prop.ty);
+
+            quote!(#(#docs)* #(#attrs)* pub #name: #ty)
+        });
+
+        let state = self.state.iter().map(|state| {
+            let docs = state.docs.as_deref().map(doc_attrs).unwrap_or_default();
+            let attrs = &state.attrs;
+            let (name, ty) = (&state.name, &state.ty);
+
+            quote!(#(#docs)* #(#attrs)* #name: #ty)
+        });
+
+        tokens.extend(quote! {
+            #(#items)*
+
+            #(#docs)*
+            #(#attrs)*
+            pub struct #name {
+                #(#fields,)*
+                #(#state,)*
+            }
+        });
+
+        // Props without defaults must be given, unless `Default` was asked for.
+        if derives_default || self.props.iter().all(|prop| prop.default.is_some()) {
+            let defaults = self.props.iter().map(|prop| {
+                let default = match prop.default {
+                    Some(ref default) => default.to_token_stream(),
+                    None => quote!(::std::default::Default::default()),
+                };
+
+                (prop, Some(default))
+            });
+            let body = self.init(defaults.collect());
+
+            tokens.extend(quote! {
+                impl ::std::default::Default for #name {
+                    fn default() -> Self {
+                        #body
+                    }
+                }
+            });
+        }
+
+        let constructor = self.constructor();
+        let methods = self.methods();
+        if constructor.is_some() || !methods.is_empty() {
+            tokens.extend(quote! {
+                impl #name {
+                    #constructor
+                    #(#methods)*
+                }
+            });
+        }
+    }
+}
+
+///
+/// The component's name, from its file's: `fruit_basket` becomes `FruitBasket`.
+///
+/// Fails if that isn't a valid identifier, as with `404` or `_`.
+///
+pub fn component_name(file_stem: &str) -> Result<syn::Ident> {
+    let name: String = file_stem
+        .split(['_', '-', '.', ' '])
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>())
+        })
+        .collect();
+
+    syn::parse_str(&name).map_err(|_| {
+        Diagnostic::new(Code::InvalidComponentName, Span::call_site(), format!("`{file_stem}` can't be used as a component name"))
+            .note("Components are named after their file, in PascalCase, which must make a valid Rust identifier")
+            .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::{quote, ToTokens};
+
+    use crate::syntax::document::Document;
+    use crate::syntax::lexer;
+
+    use crate::syntax::diagnostics::Code;
+
+    use super::{component_name, Component};
+
+    fn generate(name: &str, source: &str) -> syn::File {
+        let document: Document = lexer::parse_str(source).expect("Valid parse");
+        let component = Component::new(component_name(name).expect("Valid name"), &document).expect("Valid component");
+
+        syn::parse2(component.to_token_stream()).expect("Valid Rust")
+    }
+
+    #[test]
+    fn fruit_basket() {
+        let file = generate("fruit_basket", r#"
+            <script>
+                use std::collections::HashMap;
+
+                #[derive(Default)]
+                extern {
+                    let mut basket: HashMap<String, usize> = Default::default();
+                }
+
+                pub fn add(fruit: impl ToString) {
+                    *basket.entry(fruit.to_string())
+                        .or_insert(0) += 1;
+                }
+            </script>
+
+            <Card>
+                <Title>Fruit bowl with {basket.len()} fruits</Title>
+            </Card>
+        "#);
+
+        let expected = quote! {
+            use std::collections::HashMap;
+
+            pub struct FruitBasket {
+                pub basket: HashMap<String, usize>,
+            }
+
+            impl ::std::default::Default for FruitBasket {
+                fn default() -> Self {
+                    Self {
+                        basket: Default::default(),
+                    }
+                }
+            }
+
+            impl FruitBasket {
+                pub fn add(&mut self, fruit: impl ToString) {
+                    *self.basket.entry(fruit.to_string())
+                        .or_insert(0) += 1;
+                }
+            }
+        };
+
+        assert_eq!(file.to_token_stream().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn queue() {
+        let file = generate("queue", r#"
+            <script>
+                #[derive(Default, Clone)]
+                extern {
+                    let mut items: Vec<String> = Default::default();
+                    let title: String;
+                }
+
+                pub fn joined<'a>(iter: impl Iterator<Item = &'a str> + 'a) -> String {
+                    iter.intersperse(", ")
+                        .fold(String::new(), |s, a| s + a)
+                }
+
+                fn summary() -> String {
+                    format!("{title}: {}", joined(items.iter().map(String::as_str)))
+                }
+            </script>
+
+            <T>Queue ({items.len()}): {joined(items.iter())}</T>
+        "#);
+
+        let expected = quote! {
+            #[derive(Clone)]
+            pub struct Queue {
+                pub items: Vec<String>,
+                pub title: String,
+            }
+
+            impl ::std::default::Default for Queue {
+                fn default() -> Self {
+                    Self {
+                        items: Default::default(),
+                        title: ::std::default::Default::default(),
+                    }
+                }
+            }
+
+            impl Queue {
+                pub fn joined<'a>(iter: impl Iterator<Item = &'a str> + 'a) -> String {
+                    iter.intersperse(", ")
+                        .fold(String::new(), |s, a| s + a)
+                }
+
+                fn summary(&mut self) -> String {
+                    format!("{title}: {}", Self::joined(self.items.iter().map(String::as_str)), title = self.title)
+                }
+            }
+        };
+
+        assert_eq!(file.to_token_stream().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn docs_and_attrs() {
+        let file = generate("player", r#"
+            <script>
+                /// Player's remaining health points.
+                extern let health: i32;
+
+                ///
+                /// Player's ability scores.
+                ///
+                #[derive(Debug)]
+                #[serde(rename_all = "camelCase")]
+                extern {
+                    /// STR
+                    #[serde(default)]
+                    let strength: i32;
+                }
+            </script>
+        "#);
+
+        // No `Default`, as `health` and `strength` must be given.
+        assert_eq!(file.items.len(), 1);
+
+        let syn::Item::Struct(ref s) = file.items[0] else { panic!("Expected struct") };
+        let attrs: Vec<_> = s.attrs.iter().map(|attr| attr.to_token_stream().to_string()).collect();
+        assert_eq!(attrs, [
+            r#"# [doc = " Player's ability scores."]"#,
+            "# [derive (Debug)]",
+            r#"# [serde (rename_all = "camelCase")]"#,
+        ]);
+
+        let fields: Vec<_> = s.fields.iter().map(|field| (field.ident.clone().unwrap().to_string(), field.attrs.len())).collect();
+        assert_eq!(fields, [("health".to_string(), 1), ("strength".to_string(), 2)]);
+    }
+
+    #[test]
+    fn state() {
+        let file = generate("counter", r#"
+            <script>
+                extern let step: u32;
+                extern let mut label: String = String::from("Count");
+
+                /// Clicks so far.
+                let mut count: u32 = 0;
+                let history: Vec<u32>;
+
+                fn click() {
+                    count += step;
+                }
+            </script>
+
+            <Button on:click={|| click()}>{label}: {count}</Button>
+        "#);
+
+        let expected = quote! {
+            pub struct Counter {
+                pub step: u32,
+                pub label: String,
+                #[doc = " Clicks so far."]
+                count: u32,
+                history: Vec<u32>,
+            }
+
+            impl Counter {
+                pub fn new(step: u32) -> Self {
+                    let label: String = String::from("Count");
+                    let count: u32 = 0;
+                    let history: Vec<u32> = ::std::default::Default::default();
+                    Self { step, label, count, history, }
+                }
+
+                fn click(&mut self) {
+                    self.count += self.step;
+                }
+            }
+        };
+
+        assert_eq!(file.to_token_stream().to_string(), expected.to_string());
+
+        let file = generate("counter", r#"
+            <script>
+                extern let step: u32 = 1;
+                let count: u32 = 0;
+                let doubled: u32 = step * 2;
+            </script>
+        "#);
+
+        // `Default` and `new()` both set up the state, after the props it may use.
+        let body = quote!(let step: u32 = 1; let count: u32 = 0; let doubled: u32 = step * 2; Self { step, count, doubled, }).to_string();
+
+        let syn::Item::Impl(ref default) = file.items[1] else { panic!("Expected impl") };
+        assert!(default.to_token_stream().to_string().contains(&body));
+        let syn::Item::Impl(ref methods) = file.items[2] else { panic!("Expected impl") };
+        assert!(methods.to_token_stream().to_string().contains(&format!("pub fn new () -> Self {{ {body} }}")));
+    }
+
+    #[test]
+    fn names() {
+        let name = |stem| component_name(stem).expect("Valid name");
+        assert_eq!(name("fruit_basket"), "FruitBasket");
+        assert_eq!(name("my-widget"), "MyWidget");
+        assert_eq!(name("Queue"), "Queue");
+
+        for stem in ["404", "2fa_prompt", "_", "--", "self", ""] {
+            let e = component_name(stem).expect_err(stem);
+            assert_eq!(e[0].code, Code::InvalidComponentName);
+        }
+    }
+}
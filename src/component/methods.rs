@@ -0,0 +1,394 @@
+//!
+//! Functions in `<script>`: any that use a prop or state (or call one that does)
+//! become `&mut self` methods, and the rest associated functions.
+//!
+//! ```rust,ignore
+//! extern let mut items: Vec<String>;
+//!
+//! fn add(item: String) { items.push(item) }         // fn add(&mut self, item: String)
+//! fn joined(items: &[String]) -> String { ... }      // Queue::joined(...), `items` is shadowed
+//! ```
+//!
+
+use std::fmt::{Debug, Display};
+
+use proc_macro2::Span;
+
+use crate::syntax::diagnostics::{Code, Diagnostic, Diagnostics, Result};
+use crate::syntax::script::{ScriptBlock, ScriptItem};
+
+use super::scope::{FieldUse, Resolver, Shadow};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FnKind {
+    ///
+    /// `fn name(&mut self, ...)`
+    ///
+    Method,
+    ///
+    /// `fn name(...)`, called as `Component::name(...)`.
+    ///
+    Associated,
+}
+
+pub struct ScriptFn {
+    pub item: syn::ItemFn,
+    pub kind: FnKind,
+    ///
+    /// Every use of a field in its body.
+    ///
+    pub uses: Vec<FieldUse>,
+    ///
+    /// Other script functions it calls, or passes along by name.
+    ///
+    pub calls: Vec<syn::Ident>,
+}
+
+impl ScriptFn {
+    pub fn name(&self) -> &syn::Ident {
+        &self.item.sig.ident
+    }
+}
+
+impl Debug for ScriptFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptFn")
+            .field("name", &self.name().to_string())
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+///
+/// Something worth pointing out about how a name was resolved.
+///
+pub enum Finding {
+    ///
+    /// A local binding hides a field of the same name.
+    ///
+    Shadowed(Shadow),
+    ///
+    /// A closure uses a field, so borrows `self` for as long as it lives.
+    ///
+    Captured {
+        ident: syn::Ident,
+        closure: Span,
+    },
+}
+
+impl Finding {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Shadowed(shadow) => shadow.binding.span(),
+            Self::Captured { ident, .. } => ident.span(),
+        }
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shadowed(shadow) => write!(f, "`{}` shadows the field of the same name", shadow.binding),
+            Self::Captured { ident, .. } => write!(f, "This closure captures `self`, to use the field `{ident}`"),
+        }
+    }
+}
+
+impl Debug for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Shadowed(shadow) => f.debug_tuple("Shadowed").field(&shadow.binding.to_string()).finish(),
+            Self::Captured { ident, .. } => f.debug_tuple("Captured").field(&ident.to_string()).finish(),
+        }
+    }
+}
+
+pub struct Classified {
+    ///
+    /// Every top-level function, in order of declaration.
+    ///
+    pub fns: Vec<ScriptFn>,
+    pub findings: Vec<Finding>,
+}
+
+impl Classified {
+    pub fn get(&self, name: &str) -> Option<&ScriptFn> {
+        self.fns.iter().find(|f| f.name() == name)
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = &ScriptFn> {
+        self.fns.iter().filter(|f| f.kind == FnKind::Method)
+    }
+
+    pub fn associated(&self) -> impl Iterator<Item = &ScriptFn> {
+        self.fns.iter().filter(|f| f.kind == FnKind::Associated)
+    }
+}
+
+impl Debug for Classified {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Classified")
+            .field("fns", &self.fns)
+            .field("findings", &self.findings)
+            .finish()
+    }
+}
+
+///
+/// Classifies every top-level `fn` in `script`, where `fields` are the names
+/// of the component's props and state.
+///
+/// Fields must be used where they can be rewritten to go through `self`,
+/// and methods can't be passed by name.
+///
+pub fn classify(script: &ScriptBlock, fields: &[syn::Ident]) -> Result<Classified> {
+    let mut fns = vec![];
+    let mut findings = vec![];
+    let mut passed = vec![];
+    let mut errors = Diagnostics::default();
+
+    for item in &script.items {
+        let ScriptItem::Item(ref item) = item else { continue };
+        let syn::Item::Fn(ref item) = **item else { continue };
+
+        let mut resolver = Resolver::new(fields);
+        resolver.visit_fn(item);
+
+        for ident in &resolver.unresolved {
+            errors.push(
+                Diagnostic::new(Code::AmbiguousMacro, ident.span(), format!("Can't tell whether `{ident}` is the field of the same name, in this macro"))
+                    .note(format!("Copy it into a local first: `let {ident} = {ident};`")),
+            );
+        }
+
+        findings.extend(resolver.shadows.into_iter().map(Finding::Shadowed));
+
+        let mut captured: Vec<(String, Span)> = vec![];
+        for u in &resolver.uses {
+            let Some(closure) = u.closure else { continue };
+
+            // Once for each field in each closure.
+            if !captured.iter().any(|(name, span)| u.ident == *name && same(*span, closure)) {
+                findings.push(Finding::Captured {
+                    ident: u.ident.clone(),
+                    closure,
+                });
+                captured.push((u.ident.to_string(), closure));
+            }
+        }
+
+        let kind = match resolver.uses.is_empty() {
+            true => FnKind::Associated,
+            false => FnKind::Method,
+        };
+
+        passed.push(resolver.passed);
+        fns.push(ScriptFn {
+            item: item.clone(),
+            kind,
+            uses: resolver.uses,
+            calls: resolver.calls,
+        });
+    }
+
+    // Only calls to other script functions matter.
+    let names: Vec<_> = fns.iter().map(|f| f.name().clone()).collect();
+    for f in &mut fns {
+        f.calls.retain(|call| names.contains(call));
+    }
+
+    // Calling a method needs `self`, so makes a method too.
+    loop {
+        let methods: Vec<_> = fns.iter().filter(|f| f.kind == FnKind::Method).map(|f| f.name().clone()).collect();
+        let mut changed = false;
+
+        for f in &mut fns {
+            if f.kind == FnKind::Associated && f.calls.iter().any(|call| methods.contains(call)) {
+                f.kind = FnKind::Method;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    for ident in passed.iter().flatten() {
+        let Some(method) = fns.iter().find(|f| f.kind == FnKind::Method && f.name() == ident) else { continue };
+
+        errors.push(
+            Diagnostic::new(Code::MethodAsValue, ident.span(), format!("`{ident}` takes `&mut self`, so can't be passed by name"))
+                .suggest(ident.span(), "Call it from a closure", closure(method)),
+        );
+    }
+
+    match errors.is_empty() {
+        true => Ok(Classified { fns, findings }),
+        false => Err(errors),
+    }
+}
+
+///
+/// `|a, b| name(a, b)`, with `name`'s own parameter names where it has them.
+///
+fn closure(f: &ScriptFn) -> String {
+    let params: Vec<_> = f
+        .item
+        .sig
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| match input {
+            syn::FnArg::Typed(arg) => match *arg.pat {
+                syn::Pat::Ident(ref p) => p.ident.to_string(),
+                _ => format!("arg{i}"),
+            },
+            syn::FnArg::Receiver(_) => format!("arg{i}"),
+        })
+        .collect();
+
+    let params = params.join(", ");
+    format!("|{params}| {}({params})", f.name())
+}
+
+fn same(a: Span, b: Span) -> bool {
+    a.start() == b.start() && a.end() == b.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::props::props;
+    use crate::syntax::diagnostics::Code;
+    use crate::syntax::script::ScriptBlock;
+
+    use super::{classify, Classified, FnKind, Finding};
+
+    fn classified(source: &str) -> Classified {
+        let script: ScriptBlock = syn::parse_str(source).expect("Valid parse");
+        let fields: Vec<_> = props(&script).expect("Valid props").into_iter().map(|p| p.name).collect();
+
+        classify(&script, &fields).expect("Valid fns")
+    }
+
+    #[test]
+    fn classify_fns() {
+        let c = classified(r#"
+            #[derive(Default)]
+            extern {
+                let mut basket: HashMap<String, usize> = Default::default();
+            }
+
+            pub fn add(fruit: impl ToString) {
+                *basket.entry(fruit.to_string())
+                    .or_insert(0) += 1;
+            }
+
+            pub fn joined<'a>(iter: impl Iterator<Item = &'a str> + 'a) -> String {
+                iter.intersperse(", ")
+                    .fold(String::new(), |s, a| s + a)
+            }
+
+            fn add_apple() {
+                add("Apple")
+            }
+
+            fn report() -> String {
+                format!("{basket:?}")
+            }
+
+            impl Fruit {
+                fn basket() {}
+            }
+        "#);
+
+        let kinds: Vec<_> = c.fns.iter().map(|f| (f.name().to_string(), f.kind)).collect();
+        assert_eq!(kinds, [
+            ("add".to_string(), FnKind::Method),
+            ("joined".to_string(), FnKind::Associated),
+            ("add_apple".to_string(), FnKind::Method),
+            ("report".to_string(), FnKind::Method),
+        ]);
+
+        assert_eq!(c.get("add_apple").expect("Function").calls.len(), 1);
+        assert_eq!(c.methods().count(), 3);
+        assert!(c.findings.is_empty());
+    }
+
+    #[test]
+    fn findings() {
+        let c = classified(r#"
+            extern let mut items: Vec<String>;
+            extern let limit: usize;
+
+            fn joined(items: &[String]) -> String {
+                items.join(", ")
+            }
+
+            fn trimmed() -> Vec<String> {
+                items.iter().map(|item| item[..limit].to_string()).collect()
+            }
+        "#);
+
+        assert_eq!(c.get("joined").map(|f| f.kind), Some(FnKind::Associated));
+        assert_eq!(c.get("trimmed").map(|f| f.kind), Some(FnKind::Method));
+
+        assert!(matches!(c.findings[..], [Finding::Shadowed(ref s), Finding::Captured { ref ident, .. }]
+            if s.binding == "items" && ident == "limit"));
+
+        assert_eq!(c.findings[0].to_string(), "`items` shadows the field of the same name");
+        assert_eq!(c.findings[0].span().start().line, 5);
+    }
+
+    #[test]
+    fn passed_by_name() {
+        let c = classified(r#"
+            extern let mut total: u32;
+
+            fn double(n: u32) -> u32 { n * 2 }
+
+            fn add(n: u32) { total += n }
+
+            fn add_doubled(ns: &[u32]) {
+                ns.iter().copied().map(double).for_each(|n| add(n))
+            }
+        "#);
+
+        assert_eq!(c.get("add_doubled").map(|f| f.kind), Some(FnKind::Method));
+
+        let script: ScriptBlock = syn::parse_str(r#"
+            extern let mut total: u32;
+
+            fn add(n: u32) { total += n }
+
+            fn add_all(ns: &[u32]) {
+                ns.iter().copied().for_each(add)
+            }
+        "#).expect("Valid parse");
+        let fields = [syn::parse_quote!(total)];
+        let e = classify(&script, &fields).expect_err("Method passed by name");
+
+        assert_eq!((e[0].code, e[0].message.as_str()), (Code::MethodAsValue, "`add` takes `&mut self`, so can't be passed by name"));
+        assert_eq!(e[0].suggestions[0].replacement, "|n| add(n)");
+    }
+
+    #[test]
+    fn ambiguous_macros() {
+        let script: ScriptBlock = syn::parse_str(r#"
+            extern let limit: usize;
+
+            fn small(n: Option<usize>) -> bool {
+                matches!(n, Some(limit) if limit < 10)
+            }
+
+            fn table() -> Table {
+                table![limit => 1]
+            }
+        "#).expect("Valid parse");
+        let fields = [syn::parse_quote!(limit)];
+        let e = classify(&script, &fields).expect_err("Ambiguous macro");
+
+        let [ref d] = e[..] else { panic!("Expected one diagnostic: {e:?}") };
+        assert_eq!((d.code, d.span.start().line), (Code::AmbiguousMacro, 9));
+    }
+}
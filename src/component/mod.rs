@@ -0,0 +1,9 @@
+//!
+//! What a component means: its props, state and methods, from its parsed [`Document`](crate::syntax::document::Document).
+//!
+pub mod props;
+pub mod scope;
+pub mod methods;
+pub mod state;
+pub mod mutability;
+pub mod codegen;
\ No newline at end of file
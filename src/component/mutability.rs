@@ -0,0 +1,197 @@
+//!
+//! Props and state not declared `mut` can't be changed after initialization,
+//! whether in markup (`on:click={|| score += 1}`, `bind:value={score}`)
+//! or in script functions.
+//!
+
+use crate::syntax::diagnostics::{Code, Diagnostic, Diagnostics, Result};
+use crate::syntax::jsx::Children;
+
+use super::methods::Classified;
+use super::props::PropDecl;
+use super::scope::{Access, FieldUse, Resolver};
+use super::state::StateDecl;
+
+///
+/// A field's name, whether it's `mut`, where it was declared, and the error for changing it anyway.
+///
+type Declared<'a> = (&'a syn::Ident, bool, &'static str, Code);
+
+///
+/// Checks every write to a prop or state, in `fns` and `markup`, is to a `mut` one.
+///
+pub fn check(props: &[PropDecl], state: &[StateDecl], fns: &Classified, markup: &Children) -> Result<()> {
+    let declared: Vec<Declared> = props
+        .iter()
+        .map(|prop| (&prop.name, prop.mutable, "in `extern`", Code::ImmutableProp))
+        .chain(state.iter().map(|state| (&state.name, state.mutable, "as state", Code::ImmutableState)))
+        .collect();
+    let fields: Vec<_> = declared.iter().map(|(name, ..)| (*name).clone()).collect();
+
+    let mut resolver = Resolver::new(&fields);
+    resolver.visit_children(markup);
+
+    let uses = fns.fns.iter().flat_map(|f| &f.uses).chain(&resolver.uses);
+    let mut errors = Diagnostics::default();
+
+    for u in uses {
+        let Some(&field) = declared.iter().find(|(name, ..)| **name == u.ident) else { continue };
+
+        if field.1 || u.access == Access::Read {
+            continue;
+        }
+
+        errors.push(immutable(u, field));
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+fn immutable(u: &FieldUse, (name, _, place, code): Declared) -> Diagnostic {
+    let message = match u.access {
+        Access::Write => format!("Cannot assign twice to immutable variable `{name}`"),
+        Access::MutBorrow => format!("Cannot borrow `{name}` as mutable, as it is not declared as mutable"),
+        Access::Bind => format!("Cannot bind to `{name}`, as it is not declared as mutable"),
+        Access::Read => unreachable!("Reads are always allowed"),
+    };
+
+    Diagnostic::new(code, u.ident.span(), message)
+        .label(name.span(), format!("`{name}` declared here, {place}"))
+        .suggest(name.span(), "Consider making this binding mutable", format!("mut {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::codegen::Component;
+    use crate::syntax::diagnostics::{Code, Diagnostic};
+    use crate::syntax::document::Document;
+    use crate::syntax::lexer;
+
+    fn check(source: &str) -> Vec<Diagnostic> {
+        let document: Document = lexer::parse_str(source).expect("Valid parse");
+        let name = syn::parse_quote!(Scoreboard);
+
+        match Component::new(name, &document) {
+            Ok(_) => vec![],
+            Err(e) => e.to_vec(),
+        }
+    }
+
+    #[test]
+    fn mutable_props() {
+        let d = check(r#"
+            <script>
+                extern let mut score: i32 = 0;
+                extern let mut names: Vec<String>;
+            </script>
+            <Button on:click={|| score += 1}>Score {score}</Button>
+            <Input bind:value={names[0]} />
+            {@let total = &mut score}
+        "#);
+
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn immutable_props() {
+        let d = check(r#"<script>
+extern let score: i32 = 0;
+</script>
+<Button on:click={|| score += 1 }>
+    Score {score}
+</Button>"#);
+
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].code, Code::ImmutableProp);
+        assert_eq!(d[0].message, "Cannot assign twice to immutable variable `score`");
+        assert_eq!((d[0].span.start().line, d[0].span.start().column), (4, 21));
+        assert_eq!((d[0].labels[0].span.start().line, d[0].labels[0].span.start().column), (2, 11));
+        assert_eq!(d[0].suggestions[0].replacement, "mut score");
+
+        let d = check(r#"
+            <script>
+                extern {
+                    let score: i32 = 0;
+                    let names: Vec<String>;
+                }
+
+                fn reset() {
+                    score = 0;
+                }
+            </script>
+            <Input bind:value={names} />
+            {#for name in names.iter()}
+                <Button on:click={|| take(&mut names)}>{name}</Button>
+            {/for}
+        "#);
+
+        let messages: Vec<_> = d.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, [
+            "Cannot assign twice to immutable variable `score`",
+            "Cannot bind to `names`, as it is not declared as mutable",
+            "Cannot borrow `names` as mutable, as it is not declared as mutable",
+        ]);
+    }
+
+    #[test]
+    fn mutating_methods() {
+        // Whether a method takes `&mut self` depends on the receiver's type, so calls are left to the Rust compiler:
+        // `swap` needs only `&self` on an `AtomicUsize`.
+        let d = check(r#"
+            <script>
+                extern let items: Vec<u32>;
+                extern let counter: Counter;
+                extern let hits: Arc<AtomicUsize>;
+
+                fn reset() {
+                    items.push(1);
+                    hits.swap(0, Ordering::SeqCst);
+                }
+            </script>
+            <Button on:click={|| counter.increment()}>+1</Button>
+        "#);
+
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn shadowed_props() {
+        let d = check(r#"
+            <script>
+                extern let score: i32 = 0;
+            </script>
+            {#for mut score in 0..10}
+                <Button on:click={move || score += 1}>{score}</Button>
+            {/for}
+            {@let mut score = 5}
+            <Input bind:value={score} />
+        "#);
+
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn immutable_state() {
+        let d = check(r#"<script>
+let mut count: usize = 0;
+let label: String = String::new();
+
+fn clicked() {
+    count += 1;
+    take(&mut label);
+}
+</script>
+<Input bind:value={label} />"#);
+
+        let messages: Vec<_> = d.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, [
+            "Cannot borrow `label` as mutable, as it is not declared as mutable",
+            "Cannot bind to `label`, as it is not declared as mutable",
+        ]);
+        assert_eq!(d[0].labels[0].message, "`label` declared here, as state");
+        assert!(d.iter().all(|d| d.code == Code::ImmutableState));
+    }
+}
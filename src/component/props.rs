@@ -0,0 +1,235 @@
+//!
+//! Props, as declared in `<script>` with `extern let` and `extern { ... }`.
+//!
+
+use std::fmt::Debug;
+
+use quote::ToTokens;
+
+use crate::syntax::diagnostics::{Code, Diagnostic, Result};
+use crate::syntax::script::{PropLet, ScriptBlock, ScriptItem};
+
+pub struct PropDecl {
+    pub name: syn::Ident,
+    pub ty: syn::Type,
+    pub default: Option<syn::Expr>,
+    pub mutable: bool,
+    ///
+    /// From `///` (or `#[doc = "..."]`), with one line per line.
+    ///
+    pub docs: Option<String>,
+    ///
+    /// Every other attribute on the prop, for its field.
+    ///
+    pub attrs: Vec<syn::Attribute>,
+    ///
+    /// The `extern { ... }` block this was declared in, if any.
+    ///
+    pub group: Option<PropGroup>,
+}
+
+///
+/// An `extern { ... }` block.
+///
+#[derive(Clone)]
+pub struct PropGroup {
+    ///
+    /// Which block this is, counting from 0 in order of declaration.
+    ///
+    pub index: usize,
+    ///
+    /// Docs for the props struct itself.
+    ///
+    pub docs: Option<String>,
+    ///
+    /// Every other attribute on the block, for the props struct itself,
+    /// such as `#[derive(...)]`.
+    ///
+    pub attrs: Vec<syn::Attribute>,
+}
+
+impl PropDecl {
+    fn new(prop: &PropLet, group: Option<PropGroup>) -> Self {
+        let (docs, attrs) = split_docs(&prop.attrs);
+
+        Self {
+            name: prop.ident.clone(),
+            ty: prop.ty.clone(),
+            default: prop.default.as_ref().map(|(_, default)| default.clone()),
+            mutable: prop.mutability.is_some(),
+            docs,
+            attrs,
+            group,
+        }
+    }
+
+    ///
+    /// Whether this was declared on its own, with `extern let`.
+    ///
+    pub fn is_singleton(&self) -> bool {
+        self.group.is_none()
+    }
+}
+
+impl Debug for PropDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("PropDecl");
+
+        s.field("name", &self.name.to_string())
+            .field("ty", &self.ty.to_token_stream().to_string())
+            .field("mutable", &self.mutable);
+
+        if let Some(ref default) = self.default {
+            s.field("default", &default.to_token_stream().to_string());
+        }
+
+        if let Some(ref group) = self.group {
+            s.field("group", &group.index);
+        }
+
+        s.finish()
+    }
+}
+
+///
+/// Every prop declared in `script`, in order of declaration.
+///
+/// Each name may only be declared once, whether on its own or in a group.
+///
+pub fn props(script: &ScriptBlock) -> Result<Vec<PropDecl>> {
+    let mut props: Vec<PropDecl> = vec![];
+    let mut groups = 0;
+
+    for item in &script.items {
+        let declared: Vec<_> = match item {
+            ScriptItem::Prop(prop) => vec![PropDecl::new(&prop.prop, None)],
+            ScriptItem::Props(block) => {
+                let (docs, attrs) = split_docs(&block.attrs);
+                let group = PropGroup {
+                    index: groups,
+                    docs,
+                    attrs,
+                };
+
+                groups += 1;
+                block.props.iter().map(|prop| PropDecl::new(prop, Some(group.clone()))).collect()
+            }
+            ScriptItem::State(_) | ScriptItem::Item(_) => continue,
+        };
+
+        for prop in declared {
+            if let Some(first) = props.iter().find(|other| other.name == prop.name) {
+                return Err(Diagnostic::new(Code::DuplicateProp, prop.name.span(), format!("Prop `{}` is declared more than once", prop.name))
+                    .label(first.name.span(), format!("`{}` first declared here", first.name))
+                    .into());
+            }
+
+            props.push(prop);
+        }
+    }
+
+    Ok(props)
+}
+
+///
+/// Docs (if any), then every other attribute.
+///
+pub(crate) fn split_docs(attrs: &[syn::Attribute]) -> (Option<String>, Vec<syn::Attribute>) {
+    let mut lines = vec![];
+    let mut others = vec![];
+
+    for attr in attrs {
+        let doc = match attr.meta {
+            syn::Meta::NameValue(ref meta) if meta.path.is_ident("doc") => match meta.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref s),
+                    ..
+                }) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match doc {
+            Some(doc) => lines.extend(doc.lines().map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_string())),
+            None => others.push(attr.clone()),
+        }
+    }
+
+    // Leaves out the empty `///` lines around the docs.
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+
+    let docs = match (first, last) {
+        (Some(first), Some(last)) => Some(lines[first..=last].join("\n")),
+        _ => None,
+    };
+
+    (docs, others)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::diagnostics::Code;
+    use crate::syntax::script::ScriptBlock;
+
+    use super::props;
+
+    #[test]
+    fn extract_props() {
+        let script: ScriptBlock = syn::parse_str(r#"
+            use serde::Deserialize;
+
+            /// Player's remaining health points.
+            extern let health: i32 = 10;
+
+            ///
+            /// Plugin from the official plugin repository.
+            ///
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            extern {
+                let mut id: String;
+
+                ///
+                /// They'll never see it comin'...
+                ///
+                #[serde(default = "https://www.youtube.com/watch?v=dQw4w9WgXcQ")]
+                let homepage: String;
+            }
+
+            extern {
+                let selected: Option<usize> = Default::default();
+            }
+        "#).expect("Valid parse");
+
+        let p = props(&script).expect("Valid props");
+        assert_eq!(p.iter().map(|p| p.name.to_string()).collect::<Vec<_>>(), ["health", "id", "homepage", "selected"]);
+
+        assert!(p[0].is_singleton() && !p[0].mutable);
+        assert_eq!(p[0].docs.as_deref(), Some("Player's remaining health points."));
+        assert!(p[0].default.is_some() && p[0].attrs.is_empty());
+
+        let group = p[1].group.as_ref().expect("Grouped");
+        assert_eq!(group.index, 0);
+        assert_eq!(group.docs.as_deref(), Some("Plugin from the official plugin repository."));
+        assert_eq!(group.attrs.len(), 2);
+        assert!(p[1].mutable && p[1].docs.is_none() && p[1].default.is_none());
+
+        assert_eq!(p[2].docs.as_deref(), Some("They'll never see it comin'..."));
+        assert!(p[2].attrs[0].path().is_ident("serde"));
+        assert_eq!(p[2].group.as_ref().map(|g| g.index), Some(0));
+
+        let group = p[3].group.as_ref().expect("Grouped");
+        assert!(group.index == 1 && group.docs.is_none() && group.attrs.is_empty());
+    }
+
+    #[test]
+    fn duplicate_props() {
+        let script: ScriptBlock = syn::parse_str("extern let score: i32;\nextern {\n    let score: u32;\n}").expect("Valid parse");
+        let e = props(&script).expect_err("Invalid props");
+
+        let d = &e[0];
+        assert_eq!((d.code, d.span.start().line, d.labels[0].span.start().line), (Code::DuplicateProp, 3, 1));
+    }
+}
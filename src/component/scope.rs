@@ -0,0 +1,1130 @@
+//!
+//! Name resolution for the Rust in a component: which identifiers refer to
+//! its fields (props and state), rather than to local bindings.
+//!
+//! [`Resolver`] only looks; when generating code, [`Rewriter`] follows the same scopes
+//! to rewrite them: `field` into `self.field`, and calls to script functions into
+//! `self.method(...)` or `Self::function(...)`.
+//!
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    Token,
+};
+
+use crate::syntax::jsx::{Attribute, AttributeValue, Attributes, Child, Children, Element, Namespace};
+use crate::syntax::logic::{AsyncHead, Condition};
+use crate::syntax::mustache::StrSegment;
+use crate::syntax::tags::Tag;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    ///
+    /// `field = ...`, `field += ...`, `field.x = ...`, `field[i] = ...`
+    ///
+    Write,
+    ///
+    /// `&mut field`, `&mut field.x`, ...
+    ///
+    MutBorrow,
+    ///
+    /// `bind:value={field}`
+    ///
+    Bind,
+}
+
+///
+/// A reference to a field.
+///
+#[derive(Clone, Debug)]
+pub struct FieldUse {
+    pub ident: syn::Ident,
+    pub access: Access,
+    ///
+    /// Span of the innermost closure this is in, if any.
+    ///
+    pub closure: Option<Span>,
+}
+
+///
+/// A local binding with the same name as a field,
+/// so the field can't be used wherever the binding can.
+///
+#[derive(Clone, Debug)]
+pub struct Shadow {
+    pub binding: syn::Ident,
+    pub field: syn::Ident,
+}
+
+///
+/// The script functions, for rewriting calls to them.
+///
+pub(crate) struct Rewrite<'a> {
+    ///
+    /// Those taking `&mut self`.
+    ///
+    pub methods: &'a [syn::Ident],
+    pub associated: &'a [syn::Ident],
+}
+
+///
+/// Names of local bindings, innermost scope last.
+///
+struct Locals(Vec<Vec<String>>);
+
+impl Locals {
+    fn new() -> Self {
+        Self(vec![vec![]])
+    }
+
+    fn contains(&self, name: &syn::Ident) -> bool {
+        self.0.iter().flatten().any(|local| name == local)
+    }
+
+    fn push(&mut self) {
+        self.0.push(vec![]);
+    }
+
+    fn pop(&mut self) {
+        self.0.pop();
+    }
+
+    ///
+    /// Binds `name` in the innermost scope.
+    ///
+    fn bind(&mut self, name: &syn::Ident) {
+        self.0.last_mut().unwrap().push(name.to_string());
+    }
+}
+
+///
+/// Finds every use of a field, without changing anything.
+///
+pub(crate) struct Resolver<'a> {
+    fields: &'a [syn::Ident],
+    locals: Locals,
+    closures: Vec<Span>,
+    ///
+    /// How the expression being visited is used.
+    ///
+    access: Access,
+    pub uses: Vec<FieldUse>,
+    pub shadows: Vec<Shadow>,
+    ///
+    /// Names called (`name(...)`) or passed along (`.map(name)`) as functions,
+    /// which aren't local bindings.
+    ///
+    pub calls: Vec<syn::Ident>,
+    ///
+    /// Of those, the ones only passed along.
+    ///
+    pub passed: Vec<syn::Ident>,
+    ///
+    /// Fields named in macros that don't take expressions, where
+    /// they can't be told apart from other names (such as in patterns).
+    ///
+    pub unresolved: Vec<syn::Ident>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(fields: &'a [syn::Ident]) -> Self {
+        Self {
+            fields,
+            locals: Locals::new(),
+            closures: vec![],
+            access: Access::Read,
+            uses: vec![],
+            shadows: vec![],
+            calls: vec![],
+            passed: vec![],
+            unresolved: vec![],
+        }
+    }
+
+    fn field(&self, name: &syn::Ident) -> Option<&'a syn::Ident> {
+        self.fields.iter().find(|field| *field == name)
+    }
+
+    ///
+    /// Binds every name in `pat` in the innermost scope.
+    ///
+    pub fn bind(&mut self, pat: &syn::Pat) {
+        for ident in bindings(pat) {
+            if let Some(field) = self.field(&ident) {
+                self.shadows.push(Shadow {
+                    binding: ident.clone(),
+                    field: field.clone(),
+                });
+            }
+
+            self.locals.bind(&ident);
+        }
+    }
+
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        self.locals.push();
+        f(self);
+        self.locals.pop();
+    }
+
+    ///
+    /// Records a use of `ident`, if it is a field.
+    ///
+    fn resolve(&mut self, ident: &syn::Ident, access: Access) -> bool {
+        let field = !self.locals.contains(ident) && self.field(ident).is_some();
+
+        if field {
+            self.uses.push(FieldUse {
+                ident: ident.clone(),
+                access,
+                closure: self.closures.last().copied(),
+            });
+        }
+
+        field
+    }
+
+    ///
+    /// Records every field named in tokens that aren't expressions,
+    /// skipping any name after a `.` or `::`.
+    ///
+    fn visit_tokens(&mut self, tokens: TokenStream) {
+        let mut after_path = false;
+
+        for tt in tokens {
+            let next_after_path = matches!(tt, TokenTree::Punct(ref p) if p.as_char() == '.' || p.as_char() == ':');
+
+            match tt {
+                TokenTree::Ident(ref ident) if !after_path && !self.locals.contains(ident) && self.field(ident).is_some() => {
+                    self.unresolved.push(ident.clone());
+                }
+                TokenTree::Group(ref group) => self.visit_tokens(group.stream()),
+                _ => {}
+            }
+
+            after_path = next_after_path;
+        }
+    }
+
+    ///
+    /// Resolves the body of a function, with its parameters bound.
+    ///
+    pub fn visit_fn(&mut self, f: &syn::ItemFn) {
+        self.scoped(|this| {
+            for input in &f.sig.inputs {
+                if let syn::FnArg::Typed(ref arg) = input {
+                    this.bind(&arg.pat);
+                }
+            }
+
+            this.visit_block(&f.block);
+        });
+    }
+
+    ///
+    /// Visits `expr` as used with `access`.
+    ///
+    fn visit_as(&mut self, expr: &syn::Expr, access: Access) {
+        self.access = access;
+        self.visit_expr(expr);
+    }
+
+    ///
+    /// Resolves every expression in markup, with the names bound by
+    /// logic blocks and `{@let ...}` in scope.
+    ///
+    pub fn visit_children(&mut self, children: &Children) {
+        self.scoped(|this| {
+            for child in children {
+                this.visit_child(child);
+            }
+        });
+    }
+
+    fn visit_child(&mut self, child: &Child) {
+        match child {
+            Child::Text(_) | Child::Comment(_) | Child::Error(_) => {}
+            Child::Element(element) => self.visit_element(element),
+            Child::Fragment(fragment) => self.visit_children(&fragment.children),
+            Child::Mustache(mustache) => self.visit_expr(&mustache.expr),
+            Child::If(block) => {
+                self.visit_branch(&block.condition, &block.children);
+
+                for branch in &block.else_ifs {
+                    self.visit_branch(&branch.condition, &branch.children);
+                }
+
+                if let Some(ref otherwise) = block.otherwise {
+                    self.visit_children(&otherwise.children);
+                }
+            }
+            Child::For(block) => {
+                self.visit_expr(&block.iter);
+                self.scoped(|this| {
+                    this.bind(&block.pat);
+                    this.visit_children(&block.children);
+                });
+
+                if let Some(ref otherwise) = block.otherwise {
+                    self.visit_children(&otherwise.children);
+                }
+            }
+            Child::Match(block) => {
+                self.visit_expr(&block.expr);
+
+                for case in &block.arms {
+                    self.scoped(|this| {
+                        this.bind(&case.pat);
+
+                        if let Some((_, ref guard)) = case.guard {
+                            this.visit_expr(guard);
+                        }
+
+                        this.visit_children(&case.children);
+                    });
+                }
+            }
+            Child::Async(block) => {
+                match block.head {
+                    AsyncHead::Future(ref future) => {
+                        self.visit_expr(future);
+                        self.visit_children(&block.children);
+                    }
+                    AsyncHead::Let { ref pat, ref future, .. } => {
+                        self.visit_expr(&future.base);
+                        self.scoped(|this| {
+                            this.bind(pat);
+                            this.visit_children(&block.children);
+                        });
+                    }
+                }
+
+                if let Some(ref then) = block.then {
+                    self.scoped(|this| {
+                        if let Some(ref pat) = then.pat {
+                            this.bind(pat);
+                        }
+
+                        this.visit_children(&then.children);
+                    });
+                }
+
+                if let Some(ref catch) = block.catch {
+                    self.scoped(|this| {
+                        this.bind(&catch.pat);
+                        this.visit_children(&catch.children);
+                    });
+                }
+            }
+            Child::Key(block) => {
+                self.visit_expr(&block.expr);
+                self.visit_children(&block.children);
+            }
+            Child::Tag(Tag::Let(tag)) => {
+                // In scope for the rest of its siblings.
+                self.visit_expr(&tag.init);
+                self.bind(&tag.pat);
+            }
+            Child::Tag(Tag::Debug(tag)) => {
+                for expr in &tag.exprs {
+                    self.visit_expr(expr);
+                }
+            }
+            Child::Tag(Tag::Macro(tag)) => self.visit_macro(&tag.mac),
+        }
+    }
+
+    fn visit_branch(&mut self, condition: &Condition, children: &Children) {
+        self.scoped(|this| {
+            match condition {
+                Condition::Let(expr) => this.visit_expr_let(expr),
+                Condition::Expr(expr) => this.visit_expr(expr),
+            }
+
+            this.visit_children(children);
+        });
+    }
+
+    fn visit_element(&mut self, element: &Element) {
+        match element {
+            Element::Closed(element) => {
+                self.visit_attributes(&element.opening.attributes);
+                self.visit_children(&element.children);
+            }
+            Element::SelfClosing(element) => self.visit_attributes(&element.attributes),
+            Element::Dynamic(element) => {
+                self.visit_expr(&element.instance.expr);
+                self.visit_attributes(&element.attributes);
+            }
+        }
+    }
+
+    fn visit_attributes(&mut self, attributes: &Attributes) {
+        for attribute in attributes {
+            let attribute = match attribute {
+                Attribute::Spread(spread) => {
+                    self.visit_expr(&spread.expr);
+                    continue;
+                }
+                Attribute::Named(named) => named,
+            };
+
+            let Some(ref initializer) = attribute.initializer else { continue };
+
+            match initializer.value {
+                AttributeValue::LitStr(ref s) => {
+                    for segment in &s.segments {
+                        if let StrSegment::Mustache(ref mustache) = segment {
+                            self.visit_expr(&mustache.expr);
+                        }
+                    }
+                }
+                AttributeValue::Expr(ref value) => match attribute.key.namespace {
+                    Some((Namespace::Bind(_), _)) => self.visit_as(&value.expr, Access::Bind),
+                    _ => self.visit_expr(&value.expr),
+                },
+                AttributeValue::Element(ref element) => self.visit_element(element),
+                AttributeValue::Fragment(ref fragment) => self.visit_children(&fragment.children),
+            }
+        }
+    }
+}
+
+///
+/// Rewrites the Rust in script functions to go through `self` and `Self`,
+/// following the same scopes as [`Resolver`].
+///
+pub(crate) struct Rewriter<'a> {
+    fields: &'a [syn::Ident],
+    rewrite: Rewrite<'a>,
+    locals: Locals,
+}
+
+impl<'a> Rewriter<'a> {
+    pub fn new(fields: &'a [syn::Ident], rewrite: Rewrite<'a>) -> Self {
+        Self {
+            fields,
+            rewrite,
+            locals: Locals::new(),
+        }
+    }
+
+    fn bind(&mut self, pat: &syn::Pat) {
+        for ident in bindings(pat) {
+            self.locals.bind(&ident);
+        }
+    }
+
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        self.locals.push();
+        f(self);
+        self.locals.pop();
+    }
+
+    fn is_field(&self, ident: &syn::Ident) -> bool {
+        !self.locals.contains(ident) && self.fields.contains(ident)
+    }
+
+    fn is_method(&self, ident: &syn::Ident) -> bool {
+        !self.locals.contains(ident) && self.rewrite.methods.contains(ident)
+    }
+
+    fn is_associated(&self, ident: &syn::Ident) -> bool {
+        !self.locals.contains(ident) && self.rewrite.associated.contains(ident)
+    }
+
+    ///
+    /// Rewrites the body of a function, with its parameters bound.
+    ///
+    pub fn visit_fn(&mut self, f: &mut syn::ItemFn) {
+        self.scoped(|this| {
+            for input in &f.sig.inputs {
+                if let syn::FnArg::Typed(ref arg) = input {
+                    this.bind(&arg.pat);
+                }
+            }
+
+            this.visit_block_mut(&mut f.block);
+        });
+    }
+}
+
+///
+/// Every name bound by `pat`.
+///
+pub(crate) fn bindings(pat: &syn::Pat) -> Vec<syn::Ident> {
+    match pat {
+        syn::Pat::Ident(p) => {
+            let mut names = vec![p.ident.clone()];
+            if let Some((_, ref sub)) = p.subpat {
+                names.extend(bindings(sub));
+            }
+            names
+        }
+        syn::Pat::Or(p) => {
+            // Every case should bind the same names, but take each once from any of them.
+            let mut names: Vec<syn::Ident> = vec![];
+            for name in p.cases.iter().flat_map(bindings) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            names
+        }
+        syn::Pat::Paren(p) => bindings(&p.pat),
+        syn::Pat::Reference(p) => bindings(&p.pat),
+        syn::Pat::Slice(p) => p.elems.iter().flat_map(bindings).collect(),
+        syn::Pat::Struct(p) => p.fields.iter().flat_map(|field| bindings(&field.pat)).collect(),
+        syn::Pat::Tuple(p) => p.elems.iter().flat_map(bindings).collect(),
+        syn::Pat::TupleStruct(p) => p.elems.iter().flat_map(bindings).collect(),
+        syn::Pat::Type(p) => bindings(&p.pat),
+        _ => vec![],
+    }
+}
+
+fn is_compound(op: &syn::BinOp) -> bool {
+    use syn::BinOp::*;
+    matches!(
+        op,
+        AddAssign(_) | SubAssign(_) | MulAssign(_) | DivAssign(_) | RemAssign(_)
+            | BitXorAssign(_) | BitAndAssign(_) | BitOrAssign(_) | ShlAssign(_) | ShrAssign(_)
+    )
+}
+
+///
+/// Names captured by a format string, as in `"{score}"` or `"{name:>8}"`.
+///
+pub(crate) fn format_captures(s: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut rest = s;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        let after = &rest[i + 1..];
+
+        if after.starts_with(&rest[i..i + 1]) {
+            rest = &after[1..];
+            continue;
+        }
+
+        if &rest[i..i + 1] == "}" {
+            rest = after;
+            continue;
+        }
+
+        let Some(end) = after.find('}') else { break };
+        let arg = after[..end].split(':').next().unwrap_or_default().trim();
+
+        if arg.starts_with(|c: char| c.is_alphabetic() || c == '_') && arg.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            names.push(arg.to_string());
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    names
+}
+
+///
+/// What a macro is given.
+///
+enum MacroBody {
+    ///
+    /// Expressions, as in `format!` or `vec!`, with the names its format string
+    /// captures (`format!("{name}")`) that aren't passed as `name = value`.
+    ///
+    Exprs(Punctuated<syn::Expr, Token![,]>, Vec<syn::Ident>),
+    Matches(Box<Matches>),
+    ///
+    /// `vec![elem; n]`
+    ///
+    Repeat(Box<Repeat>),
+    ///
+    /// Anything else, which is left alone.
+    ///
+    Other,
+}
+
+impl MacroBody {
+    fn new(mac: &syn::Macro) -> Self {
+        if mac.path.is_ident("matches") {
+            return mac.parse_body().map_or(Self::Other, Self::Matches);
+        }
+
+        let Ok(args) = mac.parse_body_with(Punctuated::<syn::Expr, Token![,]>::parse_terminated) else {
+            return match mac.path.is_ident("vec") {
+                true => mac.parse_body().map_or(Self::Other, Self::Repeat),
+                false => Self::Other,
+            };
+        };
+
+        let Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(ref s),
+            ..
+        })) = args.iter().nth(format_string_index(mac))
+        else {
+            return Self::Exprs(args, vec![]);
+        };
+
+        let mut captured: Vec<syn::Ident> = vec![];
+
+        for name in format_captures(&s.value()) {
+            let is_named = args.iter().any(|arg| matches!(arg, syn::Expr::Assign(assign) if arg_name(assign).is_some_and(|ident| ident == &name)));
+
+            if !is_named && !captured.iter().any(|ident| ident == &name) {
+                captured.push(syn::Ident::new(&name, s.span()));
+            }
+        }
+
+        Self::Exprs(args, captured)
+    }
+}
+
+///
+/// Which argument is the format string, for the macros in `std` taking one
+/// (and first, for any other).
+///
+fn format_string_index(mac: &syn::Macro) -> usize {
+    let name = mac.path.segments.last().map(|segment| segment.ident.to_string()).unwrap_or_default();
+
+    match name.as_str() {
+        "write" | "writeln" | "assert" | "debug_assert" => 1,
+        "assert_eq" | "assert_ne" | "debug_assert_eq" | "debug_assert_ne" => 2,
+        _ => 0,
+    }
+}
+
+///
+/// The name in `name = value`, as passed to `format!` and friends.
+///
+fn arg_name(assign: &syn::ExprAssign) -> Option<&syn::Ident> {
+    match *assign.left {
+        syn::Expr::Path(ref path) => path.path.get_ident(),
+        _ => None,
+    }
+}
+
+///
+/// `matches!(expr, pat if guard)`
+///
+struct Matches {
+    expr: syn::Expr,
+    comma: Token![,],
+    pat: syn::Pat,
+    guard: Option<(Token![if], syn::Expr)>,
+    trailing: Option<Token![,]>,
+}
+
+impl syn::parse::Parse for Matches {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            expr: input.parse()?,
+            comma: input.parse()?,
+            pat: syn::Pat::parse_multi_with_leading_vert(input)?,
+            guard: match input.peek(Token![if]) {
+                true => Some((input.parse()?, input.parse()?)),
+                false => None,
+            },
+            trailing: input.parse()?,
+        })
+    }
+}
+
+///
+/// `elem; n`, as in `vec![elem; n]`.
+///
+struct Repeat {
+    elem: syn::Expr,
+    semi: Token![;],
+    len: syn::Expr,
+}
+
+impl syn::parse::Parse for Repeat {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            elem: input.parse()?,
+            semi: input.parse()?,
+            len: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for Repeat {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.elem.to_tokens(tokens);
+        self.semi.to_tokens(tokens);
+        self.len.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Matches {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.expr.to_tokens(tokens);
+        self.comma.to_tokens(tokens);
+        self.pat.to_tokens(tokens);
+
+        if let Some((ref if_token, ref guard)) = self.guard {
+            if_token.to_tokens(tokens);
+            guard.to_tokens(tokens);
+        }
+
+        self.trailing.to_tokens(tokens);
+    }
+}
+
+impl<'ast> Visit<'ast> for Resolver<'_> {
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        self.scoped(|this| visit::visit_block(this, block));
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        // The initializer can't see what it binds.
+        if let Some(ref init) = local.init {
+            self.visit_expr(&init.expr);
+
+            if let Some((_, ref diverge)) = init.diverge {
+                self.visit_expr(diverge);
+            }
+        }
+
+        self.bind(&local.pat);
+    }
+
+    fn visit_expr_closure(&mut self, closure: &'ast syn::ExprClosure) {
+        self.scoped(|this| {
+            for input in &closure.inputs {
+                this.bind(input);
+            }
+
+            this.closures.push(closure.span());
+            this.visit_expr(&closure.body);
+            this.closures.pop();
+        });
+    }
+
+    fn visit_arm(&mut self, arm: &'ast syn::Arm) {
+        self.scoped(|this| {
+            this.bind(&arm.pat);
+
+            if let Some((_, ref guard)) = arm.guard {
+                this.visit_expr(guard);
+            }
+
+            this.visit_expr(&arm.body);
+        });
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast syn::ExprIf) {
+        // `if let` bindings are only in scope for the `then` branch.
+        self.scoped(|this| {
+            this.visit_expr(&expr.cond);
+            this.visit_block(&expr.then_branch);
+        });
+
+        if let Some((_, ref otherwise)) = expr.else_branch {
+            self.visit_expr(otherwise);
+        }
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.scoped(|this| {
+            this.visit_expr(&expr.cond);
+            this.visit_block(&expr.body);
+        });
+    }
+
+    fn visit_expr_let(&mut self, expr: &'ast syn::ExprLet) {
+        self.visit_expr(&expr.expr);
+        self.bind(&expr.pat);
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+        self.visit_expr(&expr.expr);
+
+        self.scoped(|this| {
+            this.bind(&expr.pat);
+            this.visit_block(&expr.body);
+        });
+    }
+
+    fn visit_item(&mut self, _: &'ast syn::Item) {
+        // Nested items can't see locals, nor `self`.
+    }
+
+    fn visit_expr(&mut self, expr: &'ast syn::Expr) {
+        let access = std::mem::replace(&mut self.access, Access::Read);
+
+        match expr {
+            syn::Expr::Path(path) if path.qself.is_none() => {
+                if let Some(ident) = path.path.get_ident() {
+                    // Anything else could be a function, passed along.
+                    if !self.resolve(ident, access) && !self.locals.contains(ident) {
+                        self.calls.push(ident.clone());
+                        self.passed.push(ident.clone());
+                    }
+
+                    return;
+                }
+            }
+            syn::Expr::Call(call) => {
+                if let syn::Expr::Path(ref path) = *call.func {
+                    if let Some(ident) = path.path.get_ident().filter(|ident| !self.locals.contains(ident) && self.field(ident).is_none()) {
+                        self.calls.push(ident.clone());
+
+                        for arg in &call.args {
+                            self.visit_expr(arg);
+                        }
+
+                        return;
+                    }
+                }
+            }
+            syn::Expr::Assign(assign) => {
+                self.visit_as(&assign.left, Access::Write);
+                self.visit_as(&assign.right, Access::Read);
+                return;
+            }
+            syn::Expr::Binary(binary) if is_compound(&binary.op) => {
+                self.visit_as(&binary.left, Access::Write);
+                self.visit_as(&binary.right, Access::Read);
+                return;
+            }
+            syn::Expr::Reference(reference) if reference.mutability.is_some() => {
+                self.visit_as(&reference.expr, Access::MutBorrow);
+                return;
+            }
+            // Places: writing to `field.x` or `field[i]` writes to `field`.
+            syn::Expr::Field(field) => {
+                self.visit_as(&field.base, access);
+                return;
+            }
+            syn::Expr::Index(index) => {
+                self.visit_as(&index.expr, access);
+                self.visit_as(&index.index, Access::Read);
+                return;
+            }
+            syn::Expr::Paren(paren) => {
+                self.visit_as(&paren.expr, access);
+                return;
+            }
+            _ => {}
+        }
+
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        match MacroBody::new(mac) {
+            MacroBody::Exprs(args, captured) => {
+                for arg in &args {
+                    match arg {
+                        syn::Expr::Assign(assign) if arg_name(assign).is_some() => self.visit_expr(&assign.right),
+                        arg => self.visit_expr(arg),
+                    }
+                }
+
+                for ident in &captured {
+                    self.resolve(ident, Access::Read);
+                }
+            }
+            MacroBody::Matches(m) => {
+                self.visit_expr(&m.expr);
+                self.scoped(|this| {
+                    this.bind(&m.pat);
+
+                    if let Some((_, ref guard)) = m.guard {
+                        this.visit_expr(guard);
+                    }
+                });
+            }
+            MacroBody::Repeat(r) => {
+                self.visit_expr(&r.elem);
+                self.visit_expr(&r.len);
+            }
+            MacroBody::Other => self.visit_tokens(mac.tokens.clone()),
+        }
+    }
+}
+
+impl VisitMut for Rewriter<'_> {
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        self.scoped(|this| visit_mut::visit_block_mut(this, block));
+    }
+
+    fn visit_local_mut(&mut self, local: &mut syn::Local) {
+        if let Some(ref mut init) = local.init {
+            self.visit_expr_mut(&mut init.expr);
+
+            if let Some((_, ref mut diverge)) = init.diverge {
+                self.visit_expr_mut(diverge);
+            }
+        }
+
+        self.bind(&local.pat);
+    }
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut syn::ExprClosure) {
+        self.scoped(|this| {
+            for input in &closure.inputs {
+                this.bind(input);
+            }
+
+            this.visit_expr_mut(&mut closure.body);
+        });
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut syn::Arm) {
+        self.scoped(|this| {
+            this.bind(&arm.pat);
+
+            if let Some((_, ref mut guard)) = arm.guard {
+                this.visit_expr_mut(guard);
+            }
+
+            this.visit_expr_mut(&mut arm.body);
+        });
+    }
+
+    fn visit_expr_if_mut(&mut self, expr: &mut syn::ExprIf) {
+        self.scoped(|this| {
+            this.visit_expr_mut(&mut expr.cond);
+            this.visit_block_mut(&mut expr.then_branch);
+        });
+
+        if let Some((_, ref mut otherwise)) = expr.else_branch {
+            self.visit_expr_mut(otherwise);
+        }
+    }
+
+    fn visit_expr_while_mut(&mut self, expr: &mut syn::ExprWhile) {
+        self.scoped(|this| {
+            this.visit_expr_mut(&mut expr.cond);
+            this.visit_block_mut(&mut expr.body);
+        });
+    }
+
+    fn visit_expr_let_mut(&mut self, expr: &mut syn::ExprLet) {
+        self.visit_expr_mut(&mut expr.expr);
+        self.bind(&expr.pat);
+    }
+
+    fn visit_expr_for_loop_mut(&mut self, expr: &mut syn::ExprForLoop) {
+        self.visit_expr_mut(&mut expr.expr);
+
+        self.scoped(|this| {
+            this.bind(&expr.pat);
+            this.visit_block_mut(&mut expr.body);
+        });
+    }
+
+    fn visit_item_mut(&mut self, _: &mut syn::Item) {}
+
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        match expr {
+            syn::Expr::Path(path) if path.qself.is_none() => {
+                if let Some(ident) = path.path.get_ident().cloned() {
+                    if self.is_field(&ident) {
+                        *expr = syn::parse_quote_spanned!(ident.span()=> self.#ident);
+                    } else if self.is_associated(&ident) {
+                        *expr = syn::parse_quote_spanned!(ident.span()=> Self::#ident);
+                    }
+
+                    return;
+                }
+            }
+            syn::Expr::Call(call) => {
+                if let syn::Expr::Path(ref path) = *call.func {
+                    if let Some(ident) = path.path.get_ident().filter(|ident| self.is_method(ident)).cloned() {
+                        for arg in &mut call.args {
+                            self.visit_expr_mut(arg);
+                        }
+
+                        let args = &call.args;
+                        *expr = syn::parse_quote_spanned!(ident.span()=> self.#ident(#args));
+                        return;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        match MacroBody::new(mac) {
+            MacroBody::Exprs(mut args, captured) => {
+                for arg in &mut args {
+                    match arg {
+                        syn::Expr::Assign(assign) if arg_name(assign).is_some() => self.visit_expr_mut(&mut assign.right),
+                        arg => self.visit_expr_mut(arg),
+                    }
+                }
+
+                // `self.field` can't be captured, so is passed by name instead.
+                for ident in captured.into_iter().filter(|ident| self.is_field(ident)) {
+                    args.push(syn::parse_quote_spanned!(ident.span()=> #ident = self.#ident));
+                }
+
+                mac.tokens = args.to_token_stream();
+            }
+            MacroBody::Matches(mut m) => {
+                self.visit_expr_mut(&mut m.expr);
+                self.scoped(|this| {
+                    this.bind(&m.pat);
+
+                    if let Some((_, ref mut guard)) = m.guard {
+                        this.visit_expr_mut(guard);
+                    }
+                });
+
+                mac.tokens = m.to_token_stream();
+            }
+            MacroBody::Repeat(mut r) => {
+                self.visit_expr_mut(&mut r.elem);
+                self.visit_expr_mut(&mut r.len);
+
+                mac.tokens = r.to_token_stream();
+            }
+            // Fields in here are reported by `Resolver` instead.
+            MacroBody::Other => {}
+        }
+    }
+
+    fn visit_field_value_mut(&mut self, field: &mut syn::FieldValue) {
+        visit_mut::visit_field_value_mut(self, field);
+
+        // `Struct { field }`, once rewritten, needs to be `Struct { field: self.field }`.
+        if field.colon_token.is_none() && !matches!(field.expr, syn::Expr::Path(_)) {
+            field.colon_token = Some(Token![:](field.member.span()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+    use syn::{visit::Visit, visit_mut::VisitMut};
+
+    use super::{format_captures, Access, Resolver, Rewriter};
+
+    fn uses(fields: &[&str], expr: &str) -> Vec<(String, Access, bool)> {
+        let fields: Vec<_> = fields.iter().map(|f| syn::Ident::new(f, proc_macro2::Span::call_site())).collect();
+        let expr: syn::Expr = syn::parse_str(expr).expect("Valid expression");
+
+        let mut resolver = Resolver::new(&fields);
+        resolver.visit_expr(&expr);
+
+        resolver
+            .uses
+            .into_iter()
+            .map(|u| (u.ident.to_string(), u.access, u.closure.is_some()))
+            .collect()
+    }
+
+    #[test]
+    fn resolve_fields() {
+        assert_eq!(uses(&["score"], "score + 1"), [("score".into(), Access::Read, false)]);
+        assert_eq!(uses(&["score"], "|| score += 1"), [("score".into(), Access::Write, true)]);
+        assert_eq!(uses(&["items"], "items[0].name = n"), [("items".into(), Access::Write, false)]);
+        assert_eq!(uses(&["items"], "take(&mut items)"), [("items".into(), Access::MutBorrow, false)]);
+        assert_eq!(uses(&["items"], "items.clear()"), [("items".into(), Access::Read, false)]);
+
+        // Shadowed.
+        assert!(uses(&["score"], "{ let score = 5; score }").is_empty());
+        assert!(uses(&["score"], "|score| score + 1").is_empty());
+        assert!(uses(&["item"], "for item in list { item.go() }").is_empty());
+        assert!(uses(&["x"], "match y { Some(x) => x, None => 0 }").is_empty());
+        assert!(uses(&["x"], "if let Some(x) = y { x } else { 0 }").is_empty());
+        assert!(uses(&["x", "z"], "match y { (x, Some(z)) | (z, Some(x)) => x + z, _ => 0 }").is_empty());
+
+        // Not shadowed.
+        assert_eq!(uses(&["score"], "{ let score = score + 1; score }").len(), 1);
+        assert_eq!(uses(&["x"], "if let Some(x) = y { x } else { x }").len(), 1);
+        assert_eq!(uses(&["x"], "{ { let x = 1; } x }").len(), 1);
+
+        // Not fields.
+        assert!(uses(&["score"], "other.score + Self::score + a::score").is_empty());
+        assert!(uses(&["score"], "{ fn f() { score } }").is_empty());
+
+        // Macros.
+        assert_eq!(uses(&["score"], r#"println!("{score:>4} {}", score)"#).len(), 2);
+        assert!(uses(&["score"], r#"println!("{score}", score = 1)"#).is_empty());
+        assert_eq!(uses(&["items"], "vec![items; 2]").len(), 1);
+
+        // Format strings, wherever they are.
+        assert_eq!(uses(&["score"], r#"write!(f, "{score}")"#).len(), 1);
+        assert_eq!(uses(&["score"], r#"writeln!(f, "{score}", score = 1)"#).len(), 0);
+        assert_eq!(uses(&["score"], r#"assert!(ok, "{score}")"#).len(), 1);
+        assert_eq!(uses(&["score"], r#"assert_eq!(a, "{b}", "{score}")"#).len(), 1);
+        assert_eq!(uses(&["score"], r#"panic!("{score:?}")"#).len(), 1);
+
+        // Patterns bind, rather than use.
+        assert!(uses(&["limit"], "matches!(n, Some(limit) if limit > 0)").is_empty());
+        assert_eq!(uses(&["limit"], "matches!(limit, Some(n) if n > 0)").len(), 1);
+    }
+
+    #[test]
+    fn or_patterns() {
+        let fields = [syn::parse_quote!(x)];
+        let expr: syn::Expr = syn::parse_quote!(match y { Ok(x) | Err(x) => x });
+
+        let mut resolver = Resolver::new(&fields);
+        resolver.visit_expr(&expr);
+
+        assert!(resolver.uses.is_empty());
+        assert_eq!(resolver.shadows.len(), 1);
+    }
+
+    #[test]
+    fn calls() {
+        let expr: syn::Expr = syn::parse_str("add(1) + list.iter().map(add2).sum() + |add3| add3(1)").expect("Valid expression");
+
+        let mut resolver = Resolver::new(&[]);
+        resolver.visit_expr(&expr);
+
+        let calls: Vec<_> = resolver.calls.iter().map(|call| call.to_string()).collect();
+        assert_eq!(calls, ["add", "list", "add2"]);
+    }
+
+    #[test]
+    fn rewrite() {
+        let fields = [syn::parse_quote!(items), syn::parse_quote!(limit)];
+        let methods = [syn::parse_quote!(add)];
+        let associated = [syn::parse_quote!(joined)];
+
+        let rewritten = |source: &str| {
+            let mut expr: syn::Expr = syn::parse_str(source).expect("Valid expression");
+            let rewrite = super::Rewrite {
+                methods: &methods,
+                associated: &associated,
+            };
+
+            Rewriter::new(&fields, rewrite).visit_expr_mut(&mut expr);
+            expr.to_token_stream().to_string()
+        };
+
+        assert_eq!(rewritten("items.len() + limit"), "self . items . len () + self . limit");
+        assert_eq!(rewritten("{ let limit = 1; add(joined(&items), limit) }"), "{ let limit = 1 ; self . add (Self :: joined (& self . items) , limit) }");
+        assert_eq!(rewritten("|items| items.len()"), "| items | items . len ()");
+        assert_eq!(rewritten("Page { items, limit: 2 }"), "Page { items : self . items , limit : 2 }");
+        assert_eq!(rewritten(r#"format!("{limit} of {}", items.len())"#), r#"format ! ("{limit} of {}" , self . items . len () , limit = self . limit)"#);
+        assert_eq!(rewritten("vec![limit; other.limit]"), "vec ! [self . limit ; other . limit]");
+        assert_eq!(rewritten(r#"writeln!(f, "{limit}")"#), r#"writeln ! (f , "{limit}" , limit = self . limit)"#);
+        assert_eq!(rewritten(r#"assert_ne!(limit, 0, "{items:?}")"#), r#"assert_ne ! (self . limit , 0 , "{items:?}" , items = self . items)"#);
+        assert_eq!(rewritten("matches!(items.first(), Some(limit) if *limit > 0)"), "matches ! (self . items . first () , Some (limit) if * limit > 0)");
+
+        // Left alone, and reported by `Resolver` instead.
+        assert_eq!(rewritten("table![limit => 1]"), "table ! [limit => 1]");
+    }
+
+    #[test]
+    fn captures() {
+        assert_eq!(format_captures("{a} {{b}} {c:?} {} {0} {d.e} }}"), ["a", "c"]);
+    }
+}
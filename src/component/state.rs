@@ -0,0 +1,146 @@
+//!
+//! Private state, as declared in `<script>` with a top-level `let`.
+//!
+//! ```rust,ignore
+//! extern let mut fruits: Vec<String>;                      // A prop, given by the parent
+//! let mut basket: FruitBasket = Default::default();       // State, only the component's own
+//! ```
+//!
+
+use std::fmt::Debug;
+
+use quote::ToTokens;
+
+use crate::syntax::diagnostics::{Code, Diagnostic, Result};
+use crate::syntax::script::{PropLet, ScriptBlock, ScriptItem};
+
+use super::props::{split_docs, PropDecl};
+
+pub struct StateDecl {
+    pub name: syn::Ident,
+    pub ty: syn::Type,
+    ///
+    /// Its initial value, set by the constructor, or `Default::default()` if none.
+    ///
+    pub init: Option<syn::Expr>,
+    pub mutable: bool,
+    ///
+    /// From `///` (or `#[doc = "..."]`), with one line per line.
+    ///
+    pub docs: Option<String>,
+    ///
+    /// Every other attribute on the binding, for its field.
+    ///
+    pub attrs: Vec<syn::Attribute>,
+}
+
+impl StateDecl {
+    fn new(state: &PropLet) -> Self {
+        let (docs, attrs) = split_docs(&state.attrs);
+
+        Self {
+            name: state.ident.clone(),
+            ty: state.ty.clone(),
+            init: state.default.as_ref().map(|(_, init)| init.clone()),
+            mutable: state.mutability.is_some(),
+            docs,
+            attrs,
+        }
+    }
+}
+
+impl Debug for StateDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("StateDecl");
+
+        s.field("name", &self.name.to_string())
+            .field("ty", &self.ty.to_token_stream().to_string())
+            .field("mutable", &self.mutable);
+
+        if let Some(ref init) = self.init {
+            s.field("init", &init.to_token_stream().to_string());
+        }
+
+        s.finish()
+    }
+}
+
+///
+/// Every piece of state declared in `script`, in order of declaration.
+///
+/// Each name may only be declared once, and not as one of `props` either.
+///
+pub fn state(script: &ScriptBlock, props: &[PropDecl]) -> Result<Vec<StateDecl>> {
+    let mut state: Vec<StateDecl> = vec![];
+
+    for item in &script.items {
+        let ScriptItem::State(ref decl) = item else { continue };
+        let decl = StateDecl::new(decl);
+
+        if let Some(prop) = props.iter().find(|prop| prop.name == decl.name) {
+            return Err(Diagnostic::new(Code::DuplicateState, decl.name.span(), format!("`{}` is already used by a prop", decl.name))
+                .label(prop.name.span(), format!("Prop `{}` declared here", prop.name))
+                .into());
+        }
+
+        if let Some(first) = state.iter().find(|other| other.name == decl.name) {
+            return Err(Diagnostic::new(Code::DuplicateState, decl.name.span(), format!("State `{}` is declared more than once", decl.name))
+                .label(first.name.span(), format!("`{}` first declared here", first.name))
+                .into());
+        }
+
+        state.push(decl);
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::props::props;
+    use crate::syntax::diagnostics::Code;
+    use crate::syntax::script::ScriptBlock;
+
+    use super::state;
+
+    #[test]
+    fn extract_state() {
+        let script: ScriptBlock = syn::parse_str(r#"
+            extern let mut fruits: Vec<String>;
+
+            /// What's in the basket so far.
+            let mut basket: FruitBasket = Default::default();
+
+            #[allow(dead_code)]
+            let visits: usize;
+
+            fn add() {}
+        "#).expect("Valid parse");
+
+        let p = props(&script).expect("Valid props");
+        let s = state(&script, &p).expect("Valid state");
+
+        assert_eq!(p.len(), 1);
+        assert_eq!(s.iter().map(|s| s.name.to_string()).collect::<Vec<_>>(), ["basket", "visits"]);
+
+        assert!(s[0].mutable && s[0].init.is_some() && s[0].attrs.is_empty());
+        assert_eq!(s[0].docs.as_deref(), Some("What's in the basket so far."));
+
+        assert!(!s[1].mutable && s[1].init.is_none() && s[1].docs.is_none());
+        assert!(s[1].attrs[0].path().is_ident("allow"));
+    }
+
+    #[test]
+    fn duplicate_state() {
+        let script: ScriptBlock = syn::parse_str("extern let score: i32;\nlet score: u32 = 0;").expect("Valid parse");
+        let e = state(&script, &props(&script).expect("Valid props")).expect_err("Invalid state");
+
+        let d = &e[0];
+        assert_eq!((d.code, d.span.start().line, d.labels[0].span.start().line), (Code::DuplicateState, 2, 1));
+        assert_eq!(d.message, "`score` is already used by a prop");
+
+        let script: ScriptBlock = syn::parse_str("let a: i32 = 0;\nlet a: i32 = 1;").expect("Valid parse");
+        let e = state(&script, &[]).expect_err("Invalid state");
+        assert_eq!((e[0].code, e[0].message.as_str()), (Code::DuplicateState, "State `a` is declared more than once"));
+    }
+}
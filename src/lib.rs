@@ -1,5 +1,6 @@
 #![feature(try_blocks)]
 //!
-//! 
-//! 
-pub mod syntax;
\ No newline at end of file
+//! Pony: a Svelte-like UI framework with Rust idiomatics.
+//!
+pub mod syntax;
+pub mod component;
\ No newline at end of file
@@ -0,0 +1,496 @@
+//!
+//! Structured diagnostics: every error the parser gives has a stable [`Code`],
+//! a primary span, and optionally secondary labels, notes and suggested fixes.
+//!
+//! Parsers return [`Diagnostics`], and `syn`'s own errors convert into them with `?`.
+//! They only become a [`syn::Error`] (for `compile_error!`) at the very end,
+//! such as in the [`syn::parse::Parse`] impls, where labels turn into errors of their own.
+//!
+
+use std::{fmt::Display, ops::Deref, str::FromStr};
+
+use proc_macro2::Span;
+use syn::parse::ParseStream;
+
+///
+/// Stable error codes, displayed as `P0001`, `P0002`, ...
+///
+/// Codes are never reused or renumbered.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Code {
+    /// Any other syntax error, such as from `syn` itself.
+    Syntax,
+    ExpectedRoot,
+    MalformedTag,
+    UnclosedElement,
+    MismatchedClosingTag,
+    UnclosedFragment,
+    InvalidDynamicElement,
+    InvalidElementName,
+    UnexpectedToken,
+    InvalidEntity,
+    InvalidString,
+    Unterminated,
+    UnclosedBlock,
+    MismatchedBlockClosing,
+    MisplacedBranch,
+    StrayBlockTag,
+    UnknownBlock,
+    ExpectedAwait,
+    InvalidTag,
+    InvalidFormatting,
+    DuplicateSection,
+    InvalidProp,
+    DuplicateProp,
+    ImmutableProp,
+    AmbiguousMacro,
+    MethodAsValue,
+    DuplicateState,
+    ImmutableState,
+    InvalidComponentName,
+}
+
+const CODES: [(Code, &str); 29] = [
+    (Code::Syntax, "P0000"),
+    (Code::ExpectedRoot, "P0001"),
+    (Code::MalformedTag, "P0002"),
+    (Code::UnclosedElement, "P0003"),
+    (Code::MismatchedClosingTag, "P0004"),
+    (Code::UnclosedFragment, "P0005"),
+    (Code::InvalidDynamicElement, "P0006"),
+    (Code::InvalidElementName, "P0007"),
+    (Code::UnexpectedToken, "P0008"),
+    (Code::InvalidEntity, "P0009"),
+    (Code::InvalidString, "P0010"),
+    (Code::Unterminated, "P0011"),
+    (Code::UnclosedBlock, "P0012"),
+    (Code::MismatchedBlockClosing, "P0013"),
+    (Code::MisplacedBranch, "P0014"),
+    (Code::StrayBlockTag, "P0015"),
+    (Code::UnknownBlock, "P0016"),
+    (Code::ExpectedAwait, "P0017"),
+    (Code::InvalidTag, "P0018"),
+    (Code::InvalidFormatting, "P0019"),
+    (Code::DuplicateSection, "P0020"),
+    (Code::InvalidProp, "P0021"),
+    (Code::DuplicateProp, "P0022"),
+    (Code::ImmutableProp, "P0023"),
+    (Code::AmbiguousMacro, "P0024"),
+    (Code::MethodAsValue, "P0025"),
+    (Code::DuplicateState, "P0026"),
+    (Code::ImmutableState, "P0027"),
+    (Code::InvalidComponentName, "P0028"),
+];
+
+impl Code {
+    pub fn as_str(self) -> &'static str {
+        CODES
+            .iter()
+            .find(|(code, _)| *code == self)
+            .map(|(_, s)| *s)
+            .expect("Every code has a number")
+    }
+
+    ///
+    /// Longer explanation of this error, with examples.
+    ///
+    pub fn explain(self) -> &'static str {
+        match self {
+            Self::Syntax => "\
+The markup could not be parsed. This is usually a Rust syntax error
+inside a mustache, attribute or logic block, such as `{a +}`.",
+            Self::ExpectedRoot => "\
+A component's markup must have a single root: one element or fragment.
+
+    <Card>...</Card>     <!-- Fine -->
+    <>...</>             <!-- Fine -->
+    Just some text       <!-- Error -->",
+            Self::MalformedTag => "\
+An element's opening tag must end in `>` (or `/>` if it has no children),
+after its name and attributes.
+
+    <Button primary>     <!-- Fine -->
+    <Button primary /    <!-- Error -->",
+            Self::UnclosedElement => "\
+An element opened with `<Name ...>` must be closed with `</Name>`,
+or written self-closing as `<Name ... />` if it has no children.",
+            Self::MismatchedClosingTag => "\
+A closing tag must have exactly the same name as the innermost open element.
+
+    <Modal.Title>Hi</Modal.Title>    <!-- Fine -->
+    <Modal.Title>Hi</Modal>          <!-- Error -->",
+            Self::UnclosedFragment => "\
+A fragment opened with `<>` must be closed with `</>`.",
+            Self::InvalidDynamicElement => "\
+`<dyn Trait self={instance} />` elements must be self-closing,
+and have exactly one `self={...}` attribute, with no modifiers.",
+            Self::InvalidElementName => "\
+Element names are either a Rust path (`<ui::Button>`), a member expression
+(`<Modal.Title>`) or a hyphenated name (`<my-widget>`), but not a mixture,
+and hyphens may not have spaces around them.",
+            Self::UnexpectedToken => "\
+`<`, `>`, `{` and `}` cannot appear in text. Use `&lt;`, `&gt;`,
+or a mustache such as `{\"{\"}` instead.",
+            Self::InvalidEntity => "\
+Character references must be a known HTML entity (`&mdash;`),
+or a valid code point (`&#169;`, `&#x1F980;`).
+For a literal `&` before something that looks like an entity, use `&amp;`.",
+            Self::InvalidString => "\
+String attributes are Rust string literals, which may contain mustaches:
+`alt=\"Picture of {name}\"`. Literal braces are escaped by doubling them,
+as in `format!`: `\"{{not a mustache}}\"`.",
+            Self::Unterminated => "\
+A comment, tag, string or brace was opened, but never closed
+before the end of the file.",
+            Self::UnclosedBlock => "\
+A logic block opened with `{#keyword ...}` must be closed with `{/keyword}`
+before its enclosing element or block is.
+
+    {#if ready}
+        Done!
+    {/if}",
+            Self::MismatchedBlockClosing => "\
+A logic block must be closed with the same keyword it was opened with:
+`{#if ...}` with `{/if}`, `{#for ...}` with `{/for}`, and so on.",
+            Self::MisplacedBranch => "\
+Branches must belong to, and be in the right order for, their block:
+`{:else}` must be last in `{#if}` and `{#for}`; `{#async}` has at most one
+`{:await}` then one `{:catch}`; `{#match}` only allows comments before
+its first `{:case ...}`.",
+            Self::StrayBlockTag => "\
+`{:...}` and `{/...}` must be inside the block they belong to.",
+            Self::UnknownBlock => "\
+The logic blocks are `{#if}`, `{#for}`, `{#match}`, `{#async}` and `{#key}`.",
+            Self::ExpectedAwait => "\
+The `{#async let pat = future.await}` shorthand must end in `.await`.",
+            Self::InvalidTag => "\
+The tags are `{@let pat = expr}`, `{@debug a, b}` and macros such as `{@println!(...)}`.",
+            Self::InvalidFormatting => "\
+Mustaches take the same formatting options as `format!`: `{price:.2}`, `{id:>8}`.",
+            Self::DuplicateSection => "\
+A component file has at most one `<script>` and one `<style>`, in any order,
+alongside its markup.",
+            Self::InvalidProp => "\
+Props are declared in `<script>` with `extern let`, one name at a time,
+always with a type, and optionally a default value:
+
+    extern let health: i32 = 10;
+    extern {
+        let mut score: i32;
+    }
+
+`extern` blocks may only contain these `let`s.",
+            Self::DuplicateProp => "\
+Each prop is a field of the component, so may only be declared once,
+whether with `extern let` or in an `extern { ... }` block.",
+            Self::ImmutableProp => "\
+Props not declared `mut` can't be changed after initialization:
+not assigned to, borrowed as `&mut`, nor bound to with `bind:`.
+
+Calls to methods taking `&mut self`, such as `items.push(x)`, can't be told
+apart from others before types are known, so are left to the Rust compiler.
+
+    extern let mut score: i32 = 0;
+    <Button on:click={|| score += 1}>Fine</Button>",
+            Self::AmbiguousMacro => "\
+Script functions become methods, so props and state are used through `self`.
+In macros taking expressions (`format!`, `vec!`, `assert!`, `matches!`, ...)
+that is done for you, but in any other, a field's name can't be told apart
+from anything else. Copy it into a local first:
+
+    let limit = limit;
+    my_macro!(limit => ...);",
+            Self::MethodAsValue => "\
+Script functions using props or state become methods taking `&mut self`,
+so can't be passed by name like other functions. Call them from a closure:
+
+    fruits.iter().for_each(|fruit| add(fruit));",
+            Self::DuplicateState => "\
+State, declared in `<script>` with a top-level `let`, is a field of the component
+too, so each name may only be declared once, and not as a prop either.
+
+    extern let score: i32;
+    let best: i32 = score;",
+            Self::ImmutableState => "\
+State not declared `mut` can't be changed after initialization, the same as props:
+not assigned to, borrowed as `&mut`, nor bound to with `bind:`.
+
+    let mut clicks: u32 = 0;
+    <Button on:click={|| clicks += 1}>Fine</Button>",
+            Self::InvalidComponentName => "\
+A component is named after its file, in PascalCase: `fruit_basket.pony` becomes
+`FruitBasket`. That name must be a valid Rust identifier, so the file's name
+can't start with a digit, be a keyword, nor be made up only of `_`, `-` and `.`.
+
+    404.pony            // Not a valid name
+    not_found.pony      // Fine: `NotFound`",
+        }
+    }
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Code {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        CODES.iter().find(|(_, code)| *code == s).map(|(code, _)| *code).ok_or(())
+    }
+}
+
+///
+/// Explanation for the code `code` (like `P0004`), if there is one.
+///
+pub fn explain(code: &str) -> Option<&'static str> {
+    code.parse::<Code>().ok().map(Code::explain)
+}
+
+///
+/// Another span relevant to a [`Diagnostic`], such as where an unclosed tag was opened.
+///
+#[derive(Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+///
+/// A fix that can be applied as is: replace the source at `span` by `replacement`.
+///
+#[derive(Clone)]
+pub struct Suggestion {
+    pub span: Span,
+    pub message: String,
+    pub replacement: String,
+}
+
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub code: Code,
+    pub span: Span,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn new(code: Code, span: Span, message: impl Display) -> Self {
+        Self {
+            code,
+            span,
+            message: message.to_string(),
+            labels: vec![],
+            notes: vec![],
+            suggestions: vec![],
+        }
+    }
+
+    ///
+    /// At the next token of `input`, as with [`syn::parse::ParseBuffer::error`].
+    ///
+    pub fn at(input: ParseStream, code: Code, message: impl Display) -> Self {
+        let e = input.error(message);
+        Self::new(code, e.span(), e)
+    }
+
+    pub fn label(mut self, span: Span, message: impl Display) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    pub fn note(mut self, message: impl Display) -> Self {
+        self.notes.push(message.to_string());
+        self
+    }
+
+    pub fn suggest(mut self, span: Span, message: impl Display, replacement: impl Display) -> Self {
+        self.suggestions.push(Suggestion {
+            span,
+            message: message.to_string(),
+            replacement: replacement.to_string(),
+        });
+        self
+    }
+
+    ///
+    /// The primary message, with any notes and suggestions after it.
+    ///
+    fn rendered(&self) -> String {
+        let mut s = self.message.clone();
+
+        for note in &self.notes {
+            s += &format!("\n= note: {note}");
+        }
+
+        for suggestion in &self.suggestions {
+            s += &format!("\n= help: {}: `{}`", suggestion.message, suggestion.replacement);
+        }
+
+        s
+    }
+}
+
+impl std::fmt::Debug for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+///
+/// Every [`Diagnostic`] found, in order.
+///
+#[derive(Clone, Default)]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+pub type Result<T> = std::result::Result<T, Diagnostics>;
+
+impl Diagnostics {
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.0.push(diagnostic);
+    }
+
+    ///
+    /// Adds every diagnostic in `other` after these.
+    ///
+    pub fn combine(&mut self, other: Diagnostics) {
+        self.0.extend(other.0);
+    }
+}
+
+impl Deref for Diagnostics {
+    type Target = [Diagnostic];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl std::fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(&self.0).finish()
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, diagnostic) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", diagnostic.rendered())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self(vec![diagnostic])
+    }
+}
+
+///
+/// Errors from `syn` itself are given [`Code::Syntax`].
+///
+impl From<syn::Error> for Diagnostics {
+    fn from(error: syn::Error) -> Self {
+        Self(error.into_iter().map(|e| Diagnostic::new(Code::Syntax, e.span(), e)).collect())
+    }
+}
+
+impl From<Diagnostics> for syn::Error {
+    fn from(diagnostics: Diagnostics) -> Self {
+        let mut errors = diagnostics.0.into_iter().flat_map(|diagnostic| {
+            let labels = diagnostic.labels.iter().map(|label| syn::Error::new(label.span, &label.message));
+            std::iter::once(syn::Error::new(diagnostic.span, diagnostic.rendered()))
+                .chain(labels.collect::<Vec<_>>())
+        });
+
+        let mut error = errors.next().unwrap_or_else(|| syn::Error::new(Span::call_site(), "Unknown error"));
+        for e in errors {
+            error.combine(e);
+        }
+
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explain, Code, CODES};
+    use crate::syntax::{jsx::Root, lexer, recovery};
+
+    #[test]
+    fn codes() {
+        for (i, (code, s)) in CODES.iter().enumerate() {
+            assert_eq!(*s, format!("P{i:04}"));
+            assert_eq!(s.parse::<Code>(), Ok(*code));
+            assert!(!code.explain().is_empty());
+        }
+
+        assert!(explain("P0004").is_some_and(|s| s.contains("closing tag")));
+        assert!(explain("P9999").is_none());
+    }
+
+    #[test]
+    fn mismatched_closing_tag() {
+        let e = lexer::parse_str::<Root>("<Modal>\n    <Title>Hi</Modal.Title>\n</Modal>").expect_err("Invalid parse");
+        let [ref d] = e[..] else { panic!("Expected one diagnostic") };
+
+        assert_eq!(d.code, Code::MismatchedClosingTag);
+        assert_eq!((d.span.start().line, d.span.start().column), (2, 13));
+
+        assert_eq!(d.labels.len(), 1);
+        assert_eq!((d.labels[0].span.start().line, d.labels[0].span.start().column), (2, 4));
+
+        assert_eq!(d.suggestions[0].replacement, "Title");
+        assert_eq!(d.suggestions[0].span.source_text().as_deref(), Some("Modal.Title"));
+
+        // Both places are in the `syn::Error` it becomes.
+        assert_eq!(syn::Error::from(e).into_iter().count(), 2);
+    }
+
+    #[test]
+    fn unclosed_block() {
+        let e = lexer::parse_str::<Root>("<T>{#if a}\n    Hi\n</T>").expect_err("Invalid parse");
+        let [ref d] = e[..] else { panic!("Expected one diagnostic") };
+
+        assert_eq!(d.code, Code::UnclosedBlock);
+        assert_eq!(d.labels[0].span.start().column, 3);
+        assert_eq!(d.suggestions[0].replacement, "{/if}");
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let e = lexer::parse_str::<Root>("<T>{a +}</T>").expect_err("Invalid parse");
+        assert_eq!(e[0].code, Code::Syntax);
+    }
+
+    #[test]
+    fn recovered_diagnostics() {
+        let r = recovery::parse_str("<Card>\n  AT&T &c;\n  <Title>Oops\n</Card>");
+        let codes: Vec<_> = r.diagnostics().iter().map(|d| d.code).collect();
+
+        assert_eq!(codes, [Code::InvalidEntity, Code::UnclosedElement]);
+    }
+}
@@ -0,0 +1,287 @@
+//!
+//! A whole component file: any markup at the top level,
+//! plus at most one `<script>` and one `<style>`, in any order.
+//!
+
+use std::fmt::Debug;
+
+use proc_macro2::TokenStream;
+use quote::TokenStreamExt;
+use syn::{parse::ParseStream, Token};
+
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::jsx::{self, parse_child, Children, ClosingElement, OpeningElement};
+use super::lexer;
+use super::parser::{self, Context, Parse as _};
+use super::script::ScriptBlock;
+
+pub struct Document {
+    pub script: Option<Script>,
+    pub style: Option<Style>,
+    pub children: Children,
+}
+
+impl syn::parse::Parse for Document {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Document {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let mut script: Option<Script> = None;
+        let mut style: Option<Style> = None;
+        let mut children = vec![];
+        let mut prev = None;
+
+        while !input.is_empty() {
+            if peek_tag(input, false, "script") {
+                let next = Script::parse(input, cx)?;
+                prev = Some(next.closing.gt.span);
+
+                match script {
+                    Some(ref first) => cx.recover(duplicate(&first.opening, &next.opening))?,
+                    None => script = Some(next),
+                }
+
+                continue;
+            }
+
+            if peek_tag(input, false, "style") {
+                let next = Style::parse(input, cx)?;
+                prev = Some(next.closing.gt.span);
+
+                match style {
+                    Some(ref first) => cx.recover(duplicate(&first.opening, &next.opening))?,
+                    None => style = Some(next),
+                }
+
+                continue;
+            }
+
+            children.push(parse_child(input, cx, &mut prev)?);
+        }
+
+        Ok(Self {
+            script,
+            style,
+            children,
+        })
+    }
+}
+
+impl Debug for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Document")
+            .field("script", &self.script)
+            .field("style", &self.style)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+fn duplicate(first: &OpeningElement, next: &OpeningElement) -> Diagnostic {
+    Diagnostic::new(
+        Code::DuplicateSection,
+        next.span(),
+        format!("A component can only have one `<{}>`", first.name),
+    )
+        .label(first.span(), format!("First `<{}>` here", first.name))
+}
+
+///
+/// Whether `<name` (or `</name` if `closing`) is next.
+///
+fn peek_tag(input: ParseStream, closing: bool, name: &str) -> bool {
+    let Some((lt, mut rest)) = input.cursor().punct() else {
+        return false;
+    };
+
+    if lt.as_char() != '<' {
+        return false;
+    }
+
+    if closing {
+        match rest.punct() {
+            Some((slash, next)) if slash.as_char() == '/' => rest = next,
+            _ => return false,
+        }
+    }
+
+    rest.ident().is_some_and(|(ident, _)| ident == name)
+}
+
+///
+/// Everything up to `</name`, for `opening`.
+///
+/// If it is never closed, it is closed at the end whilst recovering.
+///
+fn parse_section(input: ParseStream, cx: &mut Context, opening: &OpeningElement, name: &str) -> Result<(TokenStream, ClosingElement)> {
+    let mut content = TokenStream::new();
+
+    while !peek_tag(input, true, name) {
+        if input.is_empty() {
+            cx.recover(opening.unclosed(input, format!("Did not find closing tag `</{name}>`")))?;
+
+            let span = input.span();
+            let closing = ClosingElement {
+                lt: Token![<](span),
+                slash: Token![/](span),
+                name: opening.name.clone(),
+                gt: Token![>](span),
+            };
+
+            return Ok((content, closing));
+        }
+
+        content.append(input.parse::<proc_macro2::TokenTree>()?);
+    }
+
+    Ok((content, ClosingElement::parse(input, cx)?))
+}
+
+///
+/// `<script> ... </script>`
+///
+pub struct Script {
+    pub opening: OpeningElement,
+    pub block: ScriptBlock,
+    pub closing: ClosingElement,
+}
+
+impl parser::Parse for Script {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let opening = OpeningElement::parse(input, cx)?;
+        let block = ScriptBlock::parse(input, cx)?;
+
+        if !(input.is_empty() || peek_tag(input, true, "script")) {
+            return Err(Diagnostic::at(input, Code::MismatchedClosingTag, "Expected closing tag `</script>` here")
+                .label(opening.span(), "`<script>` opened here")
+                .into());
+        }
+
+        let (_, closing) = parse_section(input, cx, &opening, "script")?;
+
+        Ok(Self {
+            opening,
+            block,
+            closing,
+        })
+    }
+}
+
+impl Debug for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Script").field(&self.block).finish()
+    }
+}
+
+///
+/// `<style> ... </style>`
+///
+/// The CSS is kept as is in `raw`.
+///
+pub struct Style {
+    pub opening: OpeningElement,
+    pub content: TokenStream,
+    pub raw: String,
+    pub closing: ClosingElement,
+}
+
+impl parser::Parse for Style {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let opening = OpeningElement::parse(input, cx)?;
+        let (content, closing) = parse_section(input, cx, &opening, "style")?;
+
+        let raw = match content.is_empty() {
+            true => String::new(),
+            false => lexer::raw_text(&content)
+                .or_else(|| jsx::source_between(opening.gt.span, true, closing.lt.span, true))
+                .unwrap_or_else(|| content.to_string()),
+        };
+
+        Ok(Self {
+            opening,
+            content,
+            raw,
+            closing,
+        })
+    }
+}
+
+impl Debug for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Style({:?})", self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::diagnostics::Code;
+    use crate::syntax::jsx::Child;
+    use crate::syntax::script::ScriptItem;
+    use crate::syntax::{lexer, parser, recovery};
+
+    use super::Document;
+
+    #[test]
+    fn parse_document() {
+        let d: Document = lexer::parse_str(r#"
+            <script>
+                extern let name: String;
+            </script>
+
+            <Title>Hello, {name}!</Title>
+            Don't forget to smile.
+            {#if name.is_empty()}<Warning />{/if}
+
+            <style>
+                .title { font-family: 'Comic Sans MS'; }
+            </style>
+        "#).expect("Valid parse");
+
+        assert!(d.script.is_some_and(|s| matches!(s.block.items[..], [ScriptItem::Prop(_)])));
+        assert!(d.style.is_some_and(|s| s.raw.contains("'Comic Sans MS'")));
+        assert!(matches!(d.children[..], [Child::Element(_), Child::Text(_), Child::If(_)]));
+
+        let d: Document = lexer::parse_str(r#"<style></style>Just text<script lang="rust"></script>"#).expect("Valid parse");
+        assert!(d.script.is_some() && d.style.is_some_and(|s| s.raw.is_empty()));
+        assert_eq!(d.children.len(), 1);
+
+        let d: Document = lexer::parse_str("").expect("Valid parse");
+        assert!(d.script.is_none() && d.style.is_none() && d.children.is_empty());
+
+        // Without the lexer too.
+        let d: Document = syn::parse_str(r#"<A /><B /><style>p { color: red }</style><script>fn f() {}</script>"#).expect("Valid parse");
+        assert!(d.style.is_some_and(|s| s.raw == "p { color: red }"));
+        assert_eq!(d.children.len(), 2);
+    }
+
+    #[test]
+    fn duplicates() {
+        let e = lexer::parse_str::<Document>("<script></script>\n<A />\n<script></script>").expect_err("Invalid parse");
+        let d = &e[0];
+        assert_eq!(d.code, Code::DuplicateSection);
+        assert_eq!(d.message, "A component can only have one `<script>`");
+        assert_eq!((d.span.start().line, d.labels[0].span.start().line), (3, 1));
+
+        let e = lexer::parse_str::<Document>("<style></style><style></style>").expect_err("Invalid parse");
+        assert_eq!(e[0].code, Code::DuplicateSection);
+
+        let e = lexer::parse_str::<Document>("<script>fn f() {}").expect_err("Invalid parse");
+        assert!(e.to_string().contains("never closed"));
+
+        let e = parser::parse_str::<Document>("<script>fn f() {}</style>").expect_err("Invalid parse");
+        assert_eq!(e[0].code, Code::MismatchedClosingTag);
+
+        let e = lexer::parse_str::<Document>("<script>extern let score = 0;</script>").expect_err("Invalid parse");
+        assert_eq!(e[0].code, Code::InvalidProp);
+
+        let r = recovery::parse_document("<style>a {}</style><A /><style>b {}</style><B>Oops");
+        assert_eq!(r.diagnostics().len(), 2);
+
+        let d = r.value.expect("Partial parse");
+        assert!(d.style.is_some_and(|s| s.raw == "a {}"));
+        assert_eq!(d.children.len(), 2);
+    }
+}
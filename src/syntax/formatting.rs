@@ -1,8 +1,11 @@
 use derive_syn_parse::Parse;
-use syn::{LitChar, LitInt, Token};
+use syn::{parse::ParseStream, LitChar, LitInt, Token};
 use std::fmt::Debug;
 
-fn flip<T, E>(o: Option<Result<T, E>>) -> Result<Option<T>, E> {
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::parser::{self, Context};
+
+fn flip<T, E>(o: Option<std::result::Result<T, E>>) -> std::result::Result<Option<T>, E> {
     match o {
         Some(Ok(x)) => Ok(Some(x)),
         Some(Err(e)) => Err(e),
@@ -24,15 +27,27 @@ pub struct Formatting {
 }
 
 impl syn::parse::Parse for Formatting {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Formatting {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
         let align = flip(Align::peek(input).then(|| input.parse()))?;
 
-        let sign = flip(Sign::peek(input).then(|| input.parse()))?;
+        let sign = flip(Sign::peek(input).then(|| Sign::parse(input, cx)))?;
 
         let pretty = flip(Pretty::peek(input).then(|| input.parse()))?;
 
         let Numbers(zero, width, precision) =
-            flip(Numbers::peek(input).then(|| input.parse()))?.unwrap_or(Numbers(None, None, None));
+            flip(Numbers::peek(input).then(|| Numbers::parse(input, cx)))?.unwrap_or(Numbers(None, None, None));
+
+        // Precision without a width (`.5`, `.*`, `.i$`).
+        let precision = match precision {
+            None if input.peek(Token![.]) => Some(DecimalPrecision::parse(input, cx)?),
+            precision => precision,
+        };
 
         Ok(Self {
             align,
@@ -41,7 +56,7 @@ impl syn::parse::Parse for Formatting {
             zero,
             width,
             precision,
-            ty: input.parse()?,
+            ty: FormatType::parse(input, cx)?,
         })
     }
 }
@@ -138,8 +153,8 @@ pub enum Sign {
     Negative(Token![-]),
 }
 
-impl syn::parse::Parse for Sign {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for Sign {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
         if input.peek(Token![+]) {
             return Ok(Self::Positive(input.parse().unwrap()));
         }
@@ -148,7 +163,7 @@ impl syn::parse::Parse for Sign {
             return Ok(Self::Negative(input.parse().unwrap()));
         }
 
-        Err(input.error("Expected `+` or `-` here!"))
+        Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected `+` or `-` here!").into())
     }
 }
 
@@ -211,15 +226,15 @@ impl Numbers {
     }
 }
 
-impl syn::parse::Parse for Numbers {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for Numbers {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
         if input.peek(syn::LitInt) {
             let i: LitInt = input.parse()?;
             let s = i.to_string();
 
             let precision = if input.peek(Token![.]) {
                 let decimal: Token![.] = input.parse()?;
-                let precision: Precision = input.parse()?;
+                let precision = Precision::parse(input, cx)?;
                 Some(DecimalPrecision { decimal, precision })
             } else {
                 None
@@ -227,7 +242,7 @@ impl syn::parse::Parse for Numbers {
 
             if s.len() > 1 {
                 return match (&s[0..1], &s[1..2]) {
-                    ("0", "0") => Err(input.error("Expected maximum one leading `0` here")),
+                    ("0", "0") => Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected maximum one leading `0` here").into()),
                     ("0", _) => Ok(Self(
                         Some(Zero(LitInt::new("0", i.span()))),
                         Some(Width(LitInt::new(&s[1..], i.span()))),
@@ -243,11 +258,11 @@ impl syn::parse::Parse for Numbers {
         if input.peek(syn::LitFloat) {
             let float: syn::LitFloat = input.parse()?;
             if !float.suffix().is_empty() {
-                return Err(input.error("Expected [`0`] [INT] [. INT] here"));
+                return Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected [`0`] [INT] [. INT] here").into());
             }
             let float_s = float.to_string();
             let Some((int_s, after)) = float_s.split_once('.') else {
-                return Err(input.error("Expected [`0`] [INT] [. INT] here"));
+                return Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected [`0`] [INT] [. INT] here").into());
             };
 
             let precision: DecimalPrecision = if after.is_empty() {
@@ -255,7 +270,7 @@ impl syn::parse::Parse for Numbers {
                     decimal: syn::token::Dot {
                         spans: [float.span()],
                     },
-                    precision: input.parse()?,
+                    precision: Precision::parse(input, cx)?,
                 }
             } else {
                 DecimalPrecision {
@@ -270,7 +285,7 @@ impl syn::parse::Parse for Numbers {
 
             if int_s.len() > 1 {
                 return match (&int_s[0..1], &int_s[1..2]) {
-                    ("0", "0") => Err(input.error("Expected maximum one leading `0` here")),
+                    ("0", "0") => Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected maximum one leading `0` here").into()),
                     ("0", _) => Ok(Self(
                         Some(Zero(LitInt::new("0", int.span()))),
                         Some(Width(LitInt::new(&int_s[1..], int.span()))),
@@ -283,7 +298,7 @@ impl syn::parse::Parse for Numbers {
             }
         }
 
-        Err(input.error("Expected [`0`] [INT] [. INT] here"))
+        Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected [`0`] [INT] [. INT] here").into())
     }
 }
 
@@ -292,11 +307,11 @@ pub struct DecimalPrecision {
     pub precision: Precision,
 }
 
-impl syn::parse::Parse for DecimalPrecision {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for DecimalPrecision {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
         Ok(Self {
             decimal: input.parse()?,
-            precision: input.parse()?,
+            precision: Precision::parse(input, cx)?,
         })
     }
 }
@@ -312,13 +327,13 @@ pub enum Precision {
     Count(Count),
 }
 
-impl syn::parse::Parse for Precision {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for Precision {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
         if input.peek(Token![*]) {
             return Ok(Self::Star(input.parse().unwrap()));
         }
 
-        Ok(Self::Count(input.parse()?))
+        Ok(Self::Count(Count::parse(input, cx)?))
     }
 }
 
@@ -336,8 +351,8 @@ pub enum Count {
     Integer(syn::LitInt),
 }
 
-impl syn::parse::Parse for Count {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for Count {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
         if input.peek(syn::LitInt) {
             return Ok(Self::Integer(input.parse().unwrap()));
         }
@@ -345,7 +360,7 @@ impl syn::parse::Parse for Count {
             return Ok(Self::Parameter(input.parse().unwrap()));
         }
 
-        Err(input.error("Expected either integer or parameter `var$` here"))
+        Err(Diagnostic::at(input, Code::InvalidFormatting, "Expected either integer or parameter `var$` here").into())
     }
 }
 
@@ -375,8 +390,8 @@ pub enum FormatType {
     Other(syn::Ident),
 }
 
-impl syn::parse::Parse for FormatType {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for FormatType {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
         if input.is_empty() {
             return Ok(Self::Display);
         }
@@ -393,10 +408,12 @@ impl syn::parse::Parse for FormatType {
                 "x" => Self::DebugLowerHex(format_chars::x(i), question),
                 "X" => Self::DebugUpperHex(format_chars::X(i), question),
                 _ => {
-                    return Err(syn::Error::new_spanned(
-                        i,
+                    return Err(Diagnostic::new(
+                        Code::InvalidFormatting,
+                        i.span(),
                         "Expected either `x` or `X` here!",
-                    ))
+                    )
+                    .into())
                 }
             });
         }
@@ -509,6 +526,39 @@ mod tests {
         syn::parse_str::<Formatting>(".applea nd$").expect_err("Invalid parse");
     }
 
+    #[test]
+    fn formatting_precision_without_width() {
+        assert!(matches!(
+            syn::parse_str("<.3").expect("Valid parse"),
+            Formatting {
+                align: Some(Align { direction: AlignDirection::Left(_), .. }),
+                width: None,
+                precision: Some(DecimalPrecision { precision: Precision::Count(Count::Integer(p)), .. }),
+                ty: FormatType::Display,
+                ..
+            } if p.base10_digits() == "3"
+        ));
+
+        assert!(matches!(
+            syn::parse_str("+#.*?").expect("Valid parse"),
+            Formatting {
+                sign: Some(Sign::Positive(_)),
+                pretty: Some(Pretty(_)),
+                zero: None,
+                width: None,
+                precision: Some(DecimalPrecision { precision: Precision::Star(_), .. }),
+                ty: FormatType::Debug(_),
+                ..
+            }
+        ));
+
+        let m: crate::syntax::mustache::Mustache = syn::parse_str("{price:.2}").expect("Valid parse");
+        let formatting = &m.formatting.expect("Formatting").formatting;
+        assert!(formatting.width.is_none() && formatting.precision.is_some());
+
+        syn::parse_str::<Formatting>("..2").expect_err("Invalid parse");
+    }
+
     #[test]
     fn formatting_width() {
         assert!(matches!(
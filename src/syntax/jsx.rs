@@ -5,9 +5,13 @@
 use std::fmt::Debug;
 
 use derive_syn_parse::Parse;
+use proc_macro2::Span;
 use quote::{ToTokens, TokenStreamExt};
 use syn::{
-    parse::ParseStream,
+    ext::IdentExt,
+    parse::{discouraged::Speculative, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
     Token,
 };
 
@@ -27,40 +31,97 @@ impl Debug for Root {
 
 impl syn::parse::Parse for Root {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Root {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
         if input.peek(Token![<]) {
             if input.peek2(Token![>]) {
-                return Ok(Self::Fragment(input.parse()?));
+                return Ok(Self::Fragment(Fragment::parse(input, cx)?));
             }
             
-            if input.peek2(syn::Ident) {
-                return Ok(Self::Element(input.parse()?));
+            if Element::peek(input) {
+                return Ok(Self::Element(Element::parse(input, cx)?));
             }
         }
 
-        Err(input.error("Expected either element or fragment here"))
+        Err(Diagnostic::at(input, Code::ExpectedRoot, "Expected either element or fragment here").into())
     }
 }
 
-use super::mustache::Mustache;
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::entities;
+use super::lexer;
+use super::logic::{self, Async, For, If, Key, Match};
+use super::mustache::{InterpolatedStr, Mustache};
+use super::parser::{self, Context, Parse as _};
+use super::recovery::{self, ErrorNode};
+use super::tags::Tag;
 
-fn parse_fragment_children(input: ParseStream) -> syn::Result<Vec<Child>> {
+fn parse_fragment_children(input: ParseStream, cx: &mut Context, opening: &FragmentOpening) -> Result<Vec<Child>> {
     let mut children = vec![];
+    let mut prev = Some(opening.gt.span);
 
-    while !(input.peek(Token![<]) && input.peek2(Token![/]) && input.peek3(Token![>])) {
-        children.push(input.parse()?);
+    while !(input.is_empty() || (input.peek(Token![<]) && input.peek2(Token![/]) && input.peek3(Token![>]))) {
+        children.push(parse_child(input, cx, &mut prev)?);
     }
 
     Ok(children)
 }
 
-#[derive(Parse)]
 pub struct Fragment {
     pub opening: FragmentOpening,
-    #[call(parse_fragment_children)]
     pub children: Children,
     pub closing: FragmentClosing,
 }
 
+impl syn::parse::Parse for Fragment {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Fragment {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let opening = input.parse()?;
+        let children = parse_fragment_children(input, cx, &opening)?;
+
+        let closing = match input.is_empty() {
+            true => {
+                let span = input.span();
+                let opening: &FragmentOpening = &opening;
+
+                cx.recover(
+                    Diagnostic::at(input, Code::UnclosedFragment, "Did not find closing tag `</>`")
+                        .label(opening.lt.span.join(opening.gt.span).unwrap_or(opening.lt.span), "Fragment opened here")
+                        .suggest(span, "Close the fragment", "</>"),
+                )?;
+
+                FragmentClosing {
+                    lt: Token![<](span),
+                    slash: Token![/](span),
+                    gt: Token![>](span),
+                }
+            }
+            false => input.parse()?,
+        };
+
+        Ok(Self {
+            opening,
+            children,
+            closing,
+        })
+    }
+}
+
+impl Fragment {
+    fn peek(input: ParseStream) -> bool {
+        input.peek(Token![<]) && input.peek2(Token![>])
+    }
+}
+
 impl Debug for Fragment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Fragment")
@@ -85,6 +146,13 @@ pub struct FragmentClosing {
 pub enum Element {
     Closed(ClosedElement),
     SelfClosing(SelfClosingElement),
+    Dynamic(DynamicElement),
+}
+
+impl Element {
+    fn peek(input: ParseStream) -> bool {
+        input.peek(Token![<]) && (input.peek2(syn::Ident) || input.peek2(Token![::]) || input.peek2(Token![dyn]))
+    }
 }
 
 impl Debug for Element {
@@ -92,27 +160,38 @@ impl Debug for Element {
         match self {
             Self::Closed(closed) => closed.fmt(f),
             Self::SelfClosing(self_closing) => self_closing.fmt(f),
+            Self::Dynamic(dynamic) => dynamic.fmt(f),
         }
     }
 }
 
 impl syn::parse::Parse for Element {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Element {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        if input.peek(Token![<]) && input.peek2(Token![dyn]) {
+            return Ok(Self::Dynamic(DynamicElement::parse(input, cx)?));
+        }
+
         let f = input.fork();
 
         let _: Token![<] = f.parse()?;
-        let _: syn::Path = f.parse()?;
-        let _: Vec<Attribute> = parse_attrs(&f)?;
+        let _ = ElementName::parse(&f, cx)?;
+        let _ = parse_attrs(&f, cx)?;
 
         if f.peek(Token![/]) {
-            return Ok(Self::SelfClosing(input.parse()?));
+            return Ok(Self::SelfClosing(SelfClosingElement::parse(input, cx)?));
         }
         
         if f.peek(Token![>]) {
-            return Ok(Self::Closed(input.parse()?));
+            return Ok(Self::Closed(ClosedElement::parse(input, cx)?));
         }
 
-        Err(input.error("Expected either opening or self-closing tag here"))
+        Err(Diagnostic::at(input, Code::MalformedTag, "Expected either opening or self-closing tag here").into())
     }
 }
 
@@ -125,7 +204,7 @@ pub struct ClosedElement {
 impl Debug for ClosedElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Element")
-            .field("name", &self.opening.name.0.to_token_stream().to_string())
+            .field("name", &self.opening.name.to_string())
             .field("attributes", &self.opening.attributes)
             .field("children", &self.children)
             .finish()
@@ -134,78 +213,256 @@ impl Debug for ClosedElement {
 
 impl syn::parse::Parse for ClosedElement {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let opening: OpeningElement = input.parse()?;
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for ClosedElement {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let opening = OpeningElement::parse(input, cx)?;
+        cx.within(&opening.name.clone(), |cx| Self::parse_children(input, cx, opening))
+    }
+}
+
+impl ClosedElement {
+    fn parse_children(input: ParseStream, cx: &mut Context, opening: OpeningElement) -> Result<Self> {
         let mut children = vec![];
+        let mut prev = Some(opening.gt.span);
         loop {
             if input.is_empty() {
-                return Err(input.error(format!("Did not find appropriate closing tag `<{}/>`", opening.name.0.to_token_stream())));
+                cx.recover(opening.unclosed(input, format!("Did not find appropriate closing tag `</{}>`", opening.name)))?;
+                return Ok(Self::implicitly_closed(opening, children, input.span()));
             }
 
-            if input.peek(Token![<]) && input.peek2(Token![/]) && input.peek3(syn::Ident) {
+            if input.peek(Token![<]) && input.peek2(Token![/]) && (input.peek3(syn::Ident) || input.peek3(Token![::])) {
                 let f = input.fork();
                 let _: Token![<] = f.parse().unwrap();
                 let _: Token![/] = f.parse().unwrap();
-                let i: syn::Path = f.parse().unwrap();
+                let name = ElementName::parse(&f, cx)?;
 
-                if i.segments
-                    .iter()
-                    .zip(opening.name.0.segments.iter())
-                    .all(|(a, b)| {
-                        a.ident == b.ident
-                    })
-                {
+                if opening.name.matches(&name) {
                     return Ok(Self {
                         opening,
                         children,
-                        closing: input.parse()?,
+                        closing: ClosingElement::parse(input, cx)?,
                     });
-                } else {
-                    return Err(input.error(format!("Expected closing tag `<{}/>` here", opening.name.0.to_token_stream())))
                 }
+
+                // Either this element was left open, or the closing tag is a stray.
+                if cx.encloses(&name) {
+                    cx.recover(opening.unclosed(input, format!("`<{}>` is never closed", opening.name)))?;
+                    return Ok(Self::implicitly_closed(opening, children, input.span()));
+                }
+
+                let e = Diagnostic::at(
+                    input,
+                    Code::MismatchedClosingTag,
+                    format!("Expected closing tag `</{}>` here, found `</{name}>`", opening.name),
+                )
+                    .label(opening.span(), format!("`<{}>` opened here", opening.name))
+                    .suggest(name.span(), "Close the innermost element", &opening.name)
+                    .into();
+
+                if cx.is_recovering() {
+                    let skipped = recovery::skip(input, cx, e)?;
+                    children.push(Child::Error(skipped));
+                    continue;
+                }
+
+                return Err(e);
             }
 
-            children.push(input.parse()?);
+            children.push(parse_child(input, cx, &mut prev)?);
+        }
+    }
+
+    ///
+    /// Closes an element left open, whilst recovering from errors.
+    ///
+    fn implicitly_closed(opening: OpeningElement, children: Children, span: Span) -> Self {
+        let closing = ClosingElement {
+            lt: Token![<](span),
+            slash: Token![/](span),
+            name: opening.name.clone(),
+            gt: Token![>](span),
+        };
+
+        Self {
+            opening,
+            children,
+            closing,
         }
     }
 }
 
-fn parse_attrs(input: ParseStream) -> syn::Result<Vec<Attribute>> {
+impl OpeningElement {
+    pub(crate) fn span(&self) -> Span {
+        self.lt.span.join(self.gt.span).unwrap_or(self.lt.span)
+    }
+
+    ///
+    /// Error for this element being left open, at `input`.
+    ///
+    pub(crate) fn unclosed(&self, input: ParseStream, message: String) -> Diagnostic {
+        Diagnostic::at(input, Code::UnclosedElement, message)
+            .label(self.span(), format!("`<{}>` opened here", self.name))
+            .suggest(input.span(), "Close it", format!("</{}>", self.name))
+    }
+}
+
+fn parse_attrs(input: ParseStream, cx: &mut Context) -> Result<Vec<Attribute>> {
     let mut t = vec![];
     while !(input.peek(Token![>]) || input.peek(Token![/])) {
-        t.push(input.parse()?);
+        t.push(Attribute::parse(input, cx)?);
     }
     Ok(t)
 }
 
-#[derive(Parse)]
 pub struct SelfClosingElement {
     pub lt: Token![<],
     pub name: ElementName,
-    #[call(parse_attrs)]
     pub attributes: Attributes,
     pub slash: Token![/],
     pub gt: Token![>],
 }
 
+impl syn::parse::Parse for SelfClosingElement {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for SelfClosingElement {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        Ok(Self {
+            lt: input.parse()?,
+            name: ElementName::parse(input, cx)?,
+            attributes: parse_attrs(input, cx)?,
+            slash: input.parse()?,
+            gt: input.parse()?,
+        })
+    }
+}
+
 impl Debug for SelfClosingElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Element")
-            .field("name", &self.name.0.to_token_stream().to_string())
+            .field("name", &self.name.to_string())
+            .field("attributes", &self.attributes)
+            .finish()
+    }
+}
+
+///
+/// Component chosen at runtime: `<dyn Component self={recipe} />`.
+///
+/// The `self={...}` attribute gives the component instance,
+/// and is kept apart from the other `attributes`.
+///
+pub struct DynamicElement {
+    pub lt: Token![<],
+    pub dyn_token: Token![dyn],
+    pub bound: syn::Path,
+    pub instance: Box<ExprAttributeValue>,
+    pub attributes: Attributes,
+    pub slash: Token![/],
+    pub gt: Token![>],
+}
+
+impl parser::Parse for DynamicElement {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let lt = input.parse()?;
+        let dyn_token: Token![dyn] = input.parse()?;
+        let bound = input.parse()?;
+
+        let mut instance: Option<(Span, _)> = None;
+        let mut attributes = vec![];
+
+        for attribute in parse_attrs(input, cx)? {
+            match attribute {
+                Attribute::Named(NamedAttribute { key, initializer })
+                    if key.namespace.is_none() && key.name == "self" =>
+                {
+                    if let Some((first, _)) = instance {
+                        return Err(Diagnostic::new(Code::InvalidDynamicElement, key.name.span(), "Duplicate `self` attribute")
+                            .label(first, "First given here")
+                            .into());
+                    }
+
+                    if !key.modifiers.is_empty() {
+                        return Err(Diagnostic::new(Code::InvalidDynamicElement, key.name.span(), "`self` attribute cannot have modifiers").into());
+                    }
+
+                    let Some(AttributeInitializer { value: AttributeValue::Expr(value), .. }) = initializer else {
+                        return Err(Diagnostic::new(
+                            Code::InvalidDynamicElement,
+                            key.name.span(),
+                            "Expected `self={...}` with the component instance here",
+                        )
+                        .into());
+                    };
+
+                    instance = Some((key.name.span(), value));
+                }
+                attribute => attributes.push(attribute),
+            }
+        }
+
+        let Some((_, instance)) = instance else {
+            return Err(Diagnostic::new(
+                Code::InvalidDynamicElement,
+                dyn_token.span,
+                "`<dyn ...>` elements need a `self={...}` attribute with the component instance",
+            )
+            .into());
+        };
+
+        if input.peek(Token![>]) {
+            return Err(Diagnostic::at(input, Code::InvalidDynamicElement, "`<dyn ...>` elements must be self-closing")
+                .suggest(input.span(), "Make it self-closing", "/>")
+                .into());
+        }
+
+        Ok(Self {
+            lt,
+            dyn_token,
+            bound,
+            instance,
+            attributes,
+            slash: input.parse()?,
+            gt: input.parse()?,
+        })
+    }
+}
+
+impl Debug for DynamicElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicElement")
+            .field("bound", &self.bound.to_token_stream().to_string())
+            .field("instance", &self.instance.expr.to_token_stream().to_string())
             .field("attributes", &self.attributes)
             .finish()
     }
 }
 
-#[derive(Parse)]
 pub struct OpeningElement {
     pub lt: Token![<],
     pub name: ElementName,
-    #[call(parse_attrs)]
     pub attributes: Attributes,
     pub gt: Token![>],
 }
 
-#[derive(Parse)]
+impl parser::Parse for OpeningElement {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        Ok(Self {
+            lt: input.parse()?,
+            name: ElementName::parse(input, cx)?,
+            attributes: parse_attrs(input, cx)?,
+            gt: input.parse()?,
+        })
+    }
+}
+
 pub struct ClosingElement {
     pub lt: Token![<],
     pub slash: Token![/],
@@ -213,27 +470,224 @@ pub struct ClosingElement {
     pub gt: Token![>],
 }
 
+impl parser::Parse for ClosingElement {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        Ok(Self {
+            lt: input.parse()?,
+            slash: input.parse()?,
+            name: ElementName::parse(input, cx)?,
+            gt: input.parse()?,
+        })
+    }
+}
+
 ///
-/// Covers all possibilities we want, including single identifiers
-/// and multiple paths:
+/// Covers all possibilities we want, including single identifiers,
+/// multiple paths and JSX member expressions:
 /// * `<Element />` (path length 1)
 /// * `<my::module::path::Element />` (path length 4)
+/// * `<Modal.Title />` (member expression)
+/// * `<my-widget />` (hyphenated)
 ///
-#[derive(Parse)]
-pub struct ElementName(pub syn::Path);
+#[derive(Clone)]
+pub enum ElementName {
+    Path(syn::Path),
+    Member(Punctuated<Identifier, Token![.]>),
+    Hyphenated(JsxIdentifier),
+}
+
+impl ElementName {
+    ///
+    /// Can a closing tag with the name `other` close this one?
+    ///
+    /// Names must be exactly the same: for paths, that includes every segment,
+    /// any leading `::`, and any generic arguments.
+    ///
+    pub fn matches(&self, other: &ElementName) -> bool {
+        match (self, other) {
+            (Self::Path(a), Self::Path(b)) => {
+                a.leading_colon.is_some() == b.leading_colon.is_some()
+                    && a.segments.len() == b.segments.len()
+                    && a.segments.iter().zip(b.segments.iter()).all(|(a, b)| {
+                        a.ident == b.ident
+                            && a.arguments.to_token_stream().to_string() == b.arguments.to_token_stream().to_string()
+                    })
+            }
+            (Self::Member(a), Self::Member(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a == b)
+            }
+            (Self::Hyphenated(a), Self::Hyphenated(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl ElementName {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Path(path) => path.span(),
+            Self::Member(members) => members.span(),
+            Self::Hyphenated(ident) => ident.span(),
+        }
+    }
+}
+
+impl parser::Parse for ElementName {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        if input.peek(syn::Ident) && input.peek2(Token![.]) {
+            return Ok(Self::Member(Punctuated::parse_separated_nonempty(input)?));
+        }
+
+        if input.peek(syn::Ident) && input.peek2(Token![-]) {
+            return Ok(Self::Hyphenated(JsxIdentifier::parse(input, cx)?));
+        }
+
+        let path = input.parse()?;
+
+        if input.peek(Token![.]) {
+            return Err(Diagnostic::at(input, Code::InvalidElementName, "Cannot mix `::` paths and `.` member expressions in element names").into());
+        }
+
+        Ok(Self::Path(path))
+    }
+}
+
+impl std::fmt::Display for ElementName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // As written if there's a source to hand, so generics read `List<&'a str>`, not `List < & 'a str >`.
+            Self::Path(path) => match path.span().source_text() {
+                Some(source) => write!(f, "{source}"),
+                None => write!(f, "{}", path.to_token_stream()),
+            },
+            Self::Member(members) => {
+                for pair in members.pairs() {
+                    write!(f, "{}", pair.value())?;
+
+                    if pair.punct().is_some() {
+                        write!(f, ".")?;
+                    }
+                }
+
+                Ok(())
+            }
+            Self::Hyphenated(ident) => write!(f, "{ident}"),
+        }
+    }
+}
 
 pub type Attributes = Vec<Attribute>;
 pub type Identifier = syn::Ident;
 
-#[derive(Parse)]
+///
+/// Identifier as per the JSX spec, which (unlike Rust's) may contain `-`:
+/// `aria-label`, `data-row-id`, `my-widget`.
+///
+/// Displays with the original spelling.
+///
+#[derive(Clone)]
+pub struct JsxIdentifier(pub Punctuated<Identifier, Token![-]>);
+
+impl JsxIdentifier {
+    ///
+    /// The plain Rust identifier, if this isn't hyphenated.
+    ///
+    pub fn as_ident(&self) -> Option<&Identifier> {
+        match self.0.len() {
+            1 => self.0.first(),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> proc_macro2::Span {
+        let first = self.0.first().unwrap().span();
+        let last = self.0.last().unwrap().span();
+        first.join(last).unwrap_or(first)
+    }
+
+    ///
+    /// Continues parsing after the identifier `first`.
+    ///
+    fn parse_rest(first: Identifier, input: ParseStream) -> Result<Self> {
+        let mut segments = Punctuated::new();
+        segments.push_value(first);
+
+        while input.peek(Token![-]) && input.peek2(Identifier::peek_any) {
+            let hyphen: Token![-] = input.parse()?;
+            let next = Identifier::parse_any(input)?;
+
+            let previous = segments.last().unwrap().span();
+            if previous.end() != hyphen.span.start() || hyphen.span.end() != next.span().start() {
+                return Err(Diagnostic::new(Code::InvalidElementName, hyphen.span, "Unexpected whitespace in hyphenated name").into());
+            }
+
+            segments.push_punct(hyphen);
+            segments.push_value(next);
+        }
+
+        Ok(Self(segments))
+    }
+}
+
+impl parser::Parse for JsxIdentifier {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        let first = Identifier::parse_any(input)?;
+        Self::parse_rest(first, input)
+    }
+}
+
+impl std::fmt::Display for JsxIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for pair in self.0.pairs() {
+            write!(f, "{}", pair.value())?;
+
+            if pair.punct().is_some() {
+                write!(f, "-")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for JsxIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl PartialEq for JsxIdentifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(other.0.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: AsRef<str> + ?Sized> PartialEq<T> for JsxIdentifier {
+    fn eq(&self, other: &T) -> bool {
+        let mut parts = other.as_ref().split('-');
+        self.0.iter().all(|ident| parts.next().is_some_and(|part| ident == part)) && parts.next().is_none()
+    }
+}
+
 pub enum Attribute {
-    #[peek(syn::token::Brace, name = "spread attribute")]
     Spread(SpreadAttribute),
-
-    #[peek(syn::Ident, name = "named attribute")]
     Named(NamedAttribute),
 }
 
+impl parser::Parse for Attribute {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        if input.peek(syn::token::Brace) {
+            return Ok(Self::Spread(input.parse()?));
+        }
+
+        if input.peek(syn::Ident::peek_any) {
+            return Ok(Self::Named(NamedAttribute::parse(input, cx)?));
+        }
+
+        Err(input.error("expected either spread attribute or named attribute").into())
+    }
+}
+
 impl Debug for Attribute {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -261,13 +715,23 @@ impl Debug for SpreadAttribute {
     }
 }
 
-#[derive(Parse)]
 pub struct NamedAttribute {
-    pub key: Identifier,
-    #[peek(syn::token::Eq)]
+    pub key: AttributeName,
     pub initializer: Option<AttributeInitializer>,
 }
 
+impl parser::Parse for NamedAttribute {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        Ok(Self {
+            key: AttributeName::parse(input, cx)?,
+            initializer: match input.peek(Token![=]) {
+                true => Some(AttributeInitializer::parse(input, cx)?),
+                false => None,
+            },
+        })
+    }
+}
+
 impl Debug for NamedAttribute {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("NamedAttribute")
@@ -276,32 +740,167 @@ impl Debug for NamedAttribute {
     }
 }
 
-#[derive(Parse)]
+///
+/// Attribute key, with an optional directive namespace and `|modifier`s:
+/// * `primary`
+/// * `bind:self`
+/// * `on:click|preventDefault|once`
+///
+pub struct AttributeName {
+    pub namespace: Option<(Namespace, Token![:])>,
+    pub name: JsxIdentifier,
+    pub modifiers: Vec<(Token![|], Identifier)>,
+}
+
+impl parser::Parse for AttributeName {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let first = Identifier::parse_any(input)?;
+
+        let (namespace, name) = if input.peek(Token![:]) && !input.peek(Token![::]) {
+            let colon = input.parse()?;
+            (Some((Namespace::from(first), colon)), JsxIdentifier::parse(input, cx)?)
+        } else {
+            (None, JsxIdentifier::parse_rest(first, input)?)
+        };
+
+        let mut modifiers = vec![];
+        while input.peek(Token![|]) {
+            modifiers.push((input.parse()?, Identifier::parse_any(input)?));
+        }
+
+        Ok(Self {
+            namespace,
+            name,
+            modifiers,
+        })
+    }
+}
+
+impl std::fmt::Display for AttributeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((ref namespace, _)) = self.namespace {
+            write!(f, "{}:", namespace.ident())?;
+        }
+
+        write!(f, "{}", self.name)?;
+
+        for (_, modifier) in &self.modifiers {
+            write!(f, "|{modifier}")?;
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// Directive namespaces: attributes without one are plain props.
+///
+pub enum Namespace {
+    /// Event handlers: `on:click={...}`.
+    On(Identifier),
+
+    /// Two-way bindings: `bind:value={...}`.
+    Bind(Identifier),
+
+    /// Conditional classes: `class:active={...}`.
+    Class(Identifier),
+
+    /// Accessibility props: `a11y:alt="..."`.
+    A11y(Identifier),
+
+    /// Any other namespace.
+    Other(Identifier),
+}
+
+impl Namespace {
+    pub fn ident(&self) -> &Identifier {
+        match self {
+            Self::On(ident)
+            | Self::Bind(ident)
+            | Self::Class(ident)
+            | Self::A11y(ident)
+            | Self::Other(ident) => ident,
+        }
+    }
+}
+
+impl From<Identifier> for Namespace {
+    fn from(ident: Identifier) -> Self {
+        match ident.to_string().as_str() {
+            "on" => Self::On(ident),
+            "bind" => Self::Bind(ident),
+            "class" => Self::Class(ident),
+            "a11y" => Self::A11y(ident),
+            _ => Self::Other(ident),
+        }
+    }
+}
+
+impl Debug for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.ident())
+    }
+}
+
 pub struct AttributeInitializer {
     pub equals: Token![=],
     pub value: AttributeValue,
 }
 
+impl parser::Parse for AttributeInitializer {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        Ok(Self {
+            equals: input.parse()?,
+            value: AttributeValue::parse(input, cx)?,
+        })
+    }
+}
+
 impl Debug for AttributeInitializer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.value.fmt(f)
     }
 }
 
-#[derive(Parse)]
 pub enum AttributeValue {
-    #[peek(syn::LitStr, name = "string literal")]
-    LitStr(syn::LitStr),
+    LitStr(InterpolatedStr),
+    Expr(Box<ExprAttributeValue>),
+    Element(Box<Element>),
+    Fragment(Box<Fragment>),
+}
+
+impl parser::Parse for AttributeValue {
+    ///
+    /// Elements and fragments as values are parsed strictly, even whilst recovering.
+    ///
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        if input.peek(syn::LitStr) {
+            return Ok(Self::LitStr(InterpolatedStr::parse(input, cx)?));
+        }
+
+        if input.peek(syn::token::Brace) {
+            return Ok(Self::Expr(input.parse()?));
+        }
 
-    #[peek(syn::token::Brace, name = "attribute value")]
-    Expr(ExprAttributeValue),
+        if Element::peek(input) {
+            return Ok(Self::Element(Box::new(Element::parse(input, &mut Context::default())?)));
+        }
+
+        if Fragment::peek(input) {
+            return Ok(Self::Fragment(Box::new(Fragment::parse(input, &mut Context::default())?)));
+        }
+
+        Err(input.error("expected one of string literal, attribute value, element, or fragment").into())
+    }
 }
 
 impl Debug for AttributeValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::LitStr(litstr) => write!(f, "{:?}", litstr.value()),
+            Self::LitStr(litstr) => write!(f, "{:?}", litstr.lit.value()),
             Self::Expr(expr) => write!(f, "{}", expr.expr.to_token_stream()),
+            Self::Element(element) => element.fmt(f),
+            Self::Fragment(fragment) => fragment.fmt(f),
         }
     }
 }
@@ -323,27 +922,102 @@ pub enum Child {
     Fragment(Fragment),
     Mustache(Mustache),
     Comment(Comment),
+    If(If),
+    For(For),
+    Match(Match),
+    Async(Box<Async>),
+    Key(Key),
+    Tag(Tag),
+    ///
+    /// Only whilst recovering from errors, see [`recovery`].
+    ///
+    Error(ErrorNode),
 }
 
 impl syn::parse::Parse for Child {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Child {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        Self::parse_after(input, cx, None)
+    }
+}
+
+impl Child {
+    ///
+    /// Same as parsing a [`Child`], where `prev` is the span of the token just before it,
+    /// so text can keep its leading whitespace.
+    ///
+    pub(crate) fn parse_after(input: ParseStream, cx: &mut Context, prev: Option<Span>) -> Result<Self> {
         if input.peek(Token![<]) && input.peek2(Token![>]) {
-            return Ok(Self::Fragment(input.parse()?));
+            return Ok(Self::Fragment(Fragment::parse(input, cx)?));
+        }
+
+        if Element::peek(input) {
+            return Ok(Self::Element(Element::parse(input, cx)?));
         }
 
-        if input.peek(Token![<]) && input.peek2(syn::Ident) {
-            return Ok(Self::Element(input.parse()?));
+        match logic::peek_block(input) {
+            Some(('@', _)) => return Ok(Self::Tag(Tag::parse(input, cx)?)),
+            Some(_) => return logic::parse_block(input, cx),
+            None => {}
         }
 
         if input.peek(syn::token::Brace) {
-            return Ok(Self::Mustache(input.parse()?));
+            return Ok(Self::Mustache(Mustache::parse(input, cx)?));
         }
 
         if Comment::peek(input) {
             return Ok(Self::Comment(input.parse()?));
         }
 
-        Ok(Self::Text(input.parse()?))
+        Ok(Self::Text(Text::parse_after(input, prev)?))
+    }
+}
+
+///
+/// Parses the next child, then moves `prev` onto its last token.
+///
+pub(crate) fn parse_child(input: ParseStream, cx: &mut Context, prev: &mut Option<Span>) -> Result<Child> {
+    let mut cursor = input.cursor();
+
+    let child = match cx.is_recovering() {
+        true => {
+            let f = input.fork();
+            match Child::parse_after(&f, cx, *prev) {
+                Ok(child) => {
+                    input.advance_to(&f);
+                    child
+                }
+                Err(e) if input.is_empty() => return Err(e),
+                Err(e) => Child::Error(recovery::skip(input, cx, e)?),
+            }
+        }
+        false => Child::parse_after(input, cx, *prev)?,
+    };
+
+    while cursor != input.cursor() {
+        let Some((tt, next)) = cursor.token_tree() else {
+            break;
+        };
+
+        *prev = Some(last_span(&tt));
+        cursor = next;
+    }
+
+    Ok(child)
+}
+
+///
+/// Span of the last character of a token, so for a group its closing delimiter.
+///
+fn last_span(tt: &proc_macro2::TokenTree) -> Span {
+    match tt {
+        proc_macro2::TokenTree::Group(group) => group.span_close(),
+        tt => tt.span(),
     }
 }
 
@@ -355,6 +1029,13 @@ impl Debug for Child {
             Self::Fragment(fragment) => fragment.fmt(f),
             Self::Mustache(mustache) => mustache.fmt(f),
             Self::Comment(comment) => comment.fmt(f),
+            Self::If(block) => block.fmt(f),
+            Self::For(block) => block.fmt(f),
+            Self::Match(block) => block.fmt(f),
+            Self::Async(block) => block.fmt(f),
+            Self::Key(block) => block.fmt(f),
+            Self::Tag(tag) => tag.fmt(f),
+            Self::Error(error) => error.fmt(f),
         }
     }
 }
@@ -362,32 +1043,137 @@ impl Debug for Child {
 ///
 /// Anything (including spaces), except `{`,`<`,`>`,`}`
 ///
-pub struct Text(proc_macro2::TokenStream);
+/// The exact source is kept in `raw` (including any whitespace around it, and undecoded entities),
+/// and [`Text::value`] gives the text as rendered.
+///
+pub struct Text {
+    pub tokens: proc_macro2::TokenStream,
+    pub raw: String,
+}
 
-impl syn::parse::Parse for Text {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for Text {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        Self::parse_after(input, None)
+    }
+}
+
+impl Text {
+    ///
+    /// `prev` is the span of the token before this text, if any,
+    /// so whitespace between it and the text is included in `raw`.
+    ///
+    pub(crate) fn parse_after(input: ParseStream, prev: Option<Span>) -> Result<Self> {
         let mut tkns = proc_macro2::TokenStream::new();
+        let first = input.span();
+        let mut last = first;
 
         // While next tokens aren't `{`, `<`, `>`, `}`.
-        while !(input.peek(Token![<]) || input.peek(Token![>]) || input.peek(syn::token::Brace)) {
-            tkns.append(input.parse::<proc_macro2::TokenTree>()?)
+        while !(input.is_empty() || input.peek(Token![<]) || input.peek(Token![>]) || input.peek(syn::token::Brace)) {
+            let tt = input.parse::<proc_macro2::TokenTree>()?;
+            last = last_span(&tt);
+            tkns.append(tt);
         }
 
 
         // Fixes the bug where invalid syntax within a ClosedElement could lead to
         // and infinite loop as Text would parse without taking any tokens.
         if tkns.is_empty() {
-            return Err(input.error(format!("Unexcepted `{}` here.", input)));
+            return Err(Diagnostic::at(input, Code::UnexpectedToken, format!("Unexcepted `{}` here.", input)).into());
         }
 
-        Ok(Self(tkns))
+        // Up to the start of whatever comes next.
+        let next = match input.cursor().group(proc_macro2::Delimiter::Brace) {
+            Some((_, span, _)) => Some(span.open()),
+            None if input.is_empty() => None,
+            None => Some(input.span()),
+        };
+
+        let raw = lexer::raw_text(&tkns)
+            .or_else(|| source_between(prev.unwrap_or(first), prev.is_some(), next.unwrap_or(last), next.is_some()))
+            .unwrap_or_else(|| tkns.to_string());
+
+        if let Err(e) = entities::decode(&raw) {
+            let span = lexer::raw_text_span(&tkns, e.range)
+                .or_else(|| first.join(last))
+                .unwrap_or(first);
+
+            return Err(Diagnostic::new(Code::InvalidEntity, span, e.message)
+                .suggest(span, "Escape the `&`", format!("&amp;{}", &e.entity[1..]))
+                .into());
+        }
+
+        Ok(Self { tokens: tkns, raw })
+    }
+
+    ///
+    /// The text as rendered, following JSX whitespace rules:
+    /// * Lines are trimmed of spaces and tabs, except before the first line and after the last.
+    /// * Lines left empty are removed.
+    /// * The remaining lines are joined with a single space.
+    /// * HTML entities (`&amp;`, `&#169;`, ...) are decoded.
+    ///
+    /// Returns `None` if nothing is left.
+    ///
+    pub fn value(&self) -> Option<String> {
+        collapse_whitespace(&self.raw).map(|value| entities::decode(&value).unwrap_or(value))
     }
 }
 
+pub(crate) fn collapse_whitespace(raw: &str) -> Option<String> {
+    let lines: Vec<_> = raw
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+
+    let last = lines.len() - 1;
+    let mut value = String::new();
+
+    for (i, mut line) in lines.into_iter().enumerate() {
+        if i != 0 {
+            line = line.trim_start_matches([' ', '\t']);
+        }
+
+        if i != last {
+            line = line.trim_end_matches([' ', '\t']);
+        }
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if !value.is_empty() {
+            value.push(' ');
+        }
+
+        value.push_str(line);
+    }
+
+    (!value.is_empty()).then_some(value)
+}
+
+///
+/// Source text from `start` to `end`, leaving out either token
+/// if it is `exclusive`.
+///
+pub(crate) fn source_between(start: Span, start_exclusive: bool, end: Span, end_exclusive: bool) -> Option<String> {
+    let source: Vec<char> = start.join(end)?.source_text()?.chars().collect();
+
+    let skip = match start_exclusive {
+        true => start.source_text()?.chars().count(),
+        false => 0,
+    };
+
+    let take = match end_exclusive {
+        true => end.source_text()?.chars().count(),
+        false => 0,
+    };
+
+    source.get(skip..source.len().checked_sub(take)?).map(|s| s.iter().collect())
+}
+
 impl Debug for Text {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Text({})", self.0)
-        // f.debug_tuple("Text").field(&self.0.to_string()).finish()
+        write!(f, "Text({:?})", self.raw)
     }
 }
 
@@ -395,14 +1181,17 @@ impl Debug for Text {
 ///
 /// HTML/XML-style Comment: `<!-- ANYTHING! -->`
 ///
+/// The exact source between `<!--` and `-->` is kept in `raw`.
+///
 pub struct Comment {
     pub open: OpenComment,
     pub contents: proc_macro2::TokenStream,
+    pub raw: String,
     pub closing: CloseComment,
 }
 
 impl Comment {
-    fn peek(input: ParseStream) -> bool {
+    pub(crate) fn peek(input: ParseStream) -> bool {
         // We need to look at 4 characters,
         //  which is more than any `input.peek` function gives us.
 
@@ -427,17 +1216,25 @@ impl syn::parse::Parse for Comment {
             contents.append::<proc_macro2::TokenTree>(input.parse()?);
         }
 
+        let open: OpenComment = open;
+        let closing: CloseComment = input.parse()?;
+
+        let raw = lexer::raw_text(&contents)
+            .or_else(|| source_between(open.minus2.span, true, closing.m1.span, true))
+            .unwrap_or_else(|| contents.to_string());
+
         Ok(Self {
             open,
             contents,
-            closing: input.parse()?,
+            raw,
+            closing,
         })
     }
 }
 
 impl Debug for Comment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Comment({})", self.contents.to_token_stream())
+        write!(f, "Comment({:?})", self.raw)
     }
 }
 
@@ -458,9 +1255,10 @@ pub struct CloseComment {
 
 #[cfg(test)]
 mod tests {
-    use crate::syntax::jsx::{ClosedElement, Element};
+    use crate::syntax::jsx::{Child, ClosedElement, Element, Fragment};
+    use crate::syntax::parser;
 
-    use super::{SelfClosingElement, Comment, Root};
+    use super::{Attribute, AttributeValue, ElementName, NamedAttribute, Namespace, SelfClosingElement, Comment, Root};
 
     #[test]
     fn parse_element() {
@@ -526,6 +1324,331 @@ mod tests {
             /Columns>"#).expect_err("Invalid parse");
     }
 
+    #[test]
+    fn attribute_namespaces() {
+        let e: SelfClosingElement = syn::parse_str(r#"
+            <FruitBasket
+                bind:self={basket}
+                on:click|preventDefault|once={|| basket.add(random_fruit())}
+                a11y:alt="A basket of fruit"
+                class:full={basket.len() > 10}
+                transition:fade
+                self={basket}
+                primary
+            />
+        "#).expect("Valid parse");
+
+        let names: Vec<_> = e.attributes
+            .iter()
+            .map(|a| match a {
+                Attribute::Named(named) => named.key.to_string(),
+                Attribute::Spread(_) => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(names, [
+            "bind:self",
+            "on:click|preventDefault|once",
+            "a11y:alt",
+            "class:full",
+            "transition:fade",
+            "self",
+            "primary",
+        ]);
+
+        let Attribute::Named(ref named) = e.attributes[0] else { unreachable!() };
+        assert!(matches!(named.key.namespace, Some((Namespace::Bind(_), _))));
+
+        let Attribute::Named(ref named) = e.attributes[1] else { unreachable!() };
+        assert!(matches!(named.key.namespace, Some((Namespace::On(_), _))));
+        assert_eq!(named.key.modifiers.len(), 2);
+
+        let Attribute::Named(ref named) = e.attributes[4] else { unreachable!() };
+        assert!(matches!(named.key.namespace, Some((Namespace::Other(_), _))));
+
+        let Attribute::Named(ref named) = e.attributes[6] else { unreachable!() };
+        assert!(named.key.namespace.is_none());
+
+        syn::parse_str::<SelfClosingElement>(r#"<A on:={x} />"#).expect_err("Invalid parse");
+        syn::parse_str::<SelfClosingElement>(r#"<A on:click|={x} />"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn element_member_test() {
+        let _: Root = syn::parse_str(r#"
+            <Modal>
+                <Modal.Title>
+                    Delete <T mono>system32</T>?
+                </Modal.Title>
+                <Modal.Footer>
+                    <Modal.Action secondary>
+                        <Button>
+                            <Icon.Cross /> {i11n("GENERIC.CANCEL")}
+                        </Button>
+                    </Modal.Action>
+                    <Modal.Action.Deep.Nested primary />
+                </Modal.Footer>
+            </Modal>
+        "#).expect("Valid parse");
+
+        let e: Element = syn::parse_str(r#"<Icon.TrashCan />"#).expect("Valid parse");
+        let Element::SelfClosing(e) = e else { panic!("Expected self-closing element") };
+        assert!(matches!(e.name, ElementName::Member(ref m) if m.len() == 2));
+        assert_eq!(e.name.to_string(), "Icon.TrashCan");
+
+        let e: Element = syn::parse_str(r#"<icon::TrashCan />"#).expect("Valid parse");
+        let Element::SelfClosing(e) = e else { panic!("Expected self-closing element") };
+        assert!(matches!(e.name, ElementName::Path(_)));
+
+        syn::parse_str::<Element>(r#"<Modal.Title>Hi</Modal.Action>"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<Modal.Title>Hi</Modal>"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<Modal.Title>Hi</Modal::Title>"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<Modal.Title>Hi</Modal.Title.Text>"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<ui::Modal.Title />"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn closing_tag_paths() {
+        let valid = [
+            r#"<ui::Button>Click</ui::Button>"#,
+            r#"<::ui::Button>Click</::ui::Button>"#,
+            r#"<List<Item>>Items</List<Item>>"#,
+            r#"<ui::List<ui::Item, 5>>Items</ui::List<ui::Item, 5>>"#,
+            r#"<ui::Card><ui::Card>Nested</ui::Card></ui::Card>"#,
+            r#"<Card><ui::Card>Same last segment</ui::Card></Card>"#,
+            r#"<a::B><B>Shadowed</B></a::B>"#,
+        ];
+
+        for source in valid {
+            syn::parse_str::<Element>(source).unwrap_or_else(|e| panic!("Valid parse of {source}: {e}"));
+        }
+
+        let invalid = [
+            // Segment count.
+            r#"<ui::Button>Click</ui>"#,
+            r#"<ui>Click</ui::Button>"#,
+            r#"<ui::Button>Click</Button>"#,
+            r#"<Button>Click</ui::Button>"#,
+            // Segments themselves.
+            r#"<ui::Button>Click</ux::Button>"#,
+            r#"<::ui::Button>Click</ui::Button>"#,
+            // Generic arguments.
+            r#"<List<Item>>Items</List>"#,
+            r#"<List>Items</List<Item>>"#,
+            r#"<List<Item>>Items</List<Other>>"#,
+            r#"<List<Item, 5>>Items</List<Item, 6>>"#,
+            // Mis-nesting.
+            r#"<a::B><B>Crossed</a::B></B>"#,
+            r#"<ui::Card><ui::Title>Crossed</ui::Card></ui::Title>"#,
+        ];
+
+        for source in invalid {
+            syn::parse_str::<Element>(source).expect_err(source);
+        }
+
+        let e = parser::parse_str::<Element>(r#"<ui::Button>Click</ui>"#).expect_err("Invalid parse");
+        let d = &e[0];
+
+        assert_eq!(d.message, "Expected closing tag `</ui::Button>` here, found `</ui>`");
+        assert_eq!(d.span.start().column, 17);
+        assert_eq!(d.labels[0].span.start().column, 0);
+        assert_eq!(d.labels[0].message, "`<ui::Button>` opened here");
+
+        let e = parser::parse_str::<Element>(r#"<List<Item, 5>>Items</List<Item>>"#).expect_err("Invalid parse");
+        let d = &e[0];
+        assert_eq!(d.message, "Expected closing tag `</List<Item, 5>>` here, found `</List<Item>>`");
+        assert_eq!(d.suggestions[0].replacement, "List<Item, 5>");
+    }
+
+    #[test]
+    fn element_name_display() {
+        let sources = [
+            "ui::List<Item, 5>",
+            "::ui::Map<&'a str, Vec<Box<dyn Fn(u8) -> u8>>>",
+            "Cell<&'static mut [u8; 4]>",
+            "Iter<Item = (u8, u8)>",
+            "Bound<T: Copy + Clone>",
+        ];
+
+        for source in sources {
+            // From the source as written, even with odd spacing.
+            let name: ElementName = parser::parse_str(source).expect("Valid parse");
+            assert_eq!(name.to_string(), source);
+
+            let spaced = source.replace(", ", " ,  ");
+            let name: ElementName = parser::parse_str(&spaced).expect("Valid parse");
+            assert_eq!(name.to_string(), spaced);
+
+            // Printed from the tokens, spaced out between each, without a source.
+            let tokens: proc_macro2::TokenStream = source.parse().expect("Valid tokens");
+            let name: ElementName = parser::parse2(respan(tokens)).expect("Valid parse");
+            assert_eq!(name.to_string().replace(' ', ""), source.replace(' ', ""));
+        }
+
+        let e = parser::parse_str::<Element>(r#"<List<&'a str>>Items</List<&'b str>>"#).expect_err("Invalid parse");
+        let d = &e[0];
+        assert_eq!(d.message, "Expected closing tag `</List<&'a str>>` here, found `</List<&'b str>>`");
+    }
+
+    fn respan(tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        tokens
+            .into_iter()
+            .map(|tt| match tt {
+                proc_macro2::TokenTree::Group(g) => {
+                    let mut group = proc_macro2::Group::new(g.delimiter(), respan(g.stream()));
+                    group.set_span(proc_macro2::Span::call_site());
+                    group.into()
+                }
+                mut tt => {
+                    tt.set_span(proc_macro2::Span::call_site());
+                    tt
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dynamic_element() {
+        let e: Element = syn::parse_str(r#"<dyn Component self={recipe} />"#).expect("Valid parse");
+        assert!(matches!(e, Element::Dynamic(ref d) if d.attributes.is_empty()));
+
+        let e: Element = syn::parse_str(r#"<dyn plugin::Widget primary self={plugins[i].widget()} on:click={|| ()} />"#).expect("Valid parse");
+        assert!(matches!(e, Element::Dynamic(ref d) if d.attributes.len() == 2));
+
+        let _: Root = syn::parse_str(r#"
+            <Recipes>
+                <dyn Component self={recipe}/>
+            </Recipes>
+        "#).expect("Valid parse");
+
+        syn::parse_str::<Element>(r#"<dyn Component />"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<dyn Component self="recipe" />"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<dyn Component self={a} self={b} />"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<dyn Component self={a}>Child</dyn>"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn hyphenated_names() {
+        let e: Element = syn::parse_str(r#"
+            <my-widget aria-label="Close" data-row-id={row.id} on:row-click={select} class="x">
+                <inner-part />
+            </my-widget>
+        "#).expect("Valid parse");
+
+        let Element::Closed(e) = e else { panic!("Expected closed element") };
+        assert!(matches!(e.opening.name, ElementName::Hyphenated(_)));
+        assert_eq!(e.opening.name.to_string(), "my-widget");
+
+        let names: Vec<_> = e.opening.attributes
+            .iter()
+            .map(|a| match a {
+                Attribute::Named(named) => named.key.to_string(),
+                Attribute::Spread(_) => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(names, ["aria-label", "data-row-id", "on:row-click", "class"]);
+
+        let Attribute::Named(ref named) = e.opening.attributes[3] else { unreachable!() };
+        assert!(named.key.name.as_ident().is_some());
+
+        let Attribute::Named(ref named) = e.opening.attributes[0] else { unreachable!() };
+        assert!(named.key.name.as_ident().is_none());
+        assert!(named.key.name == "aria-label");
+
+        syn::parse_str::<Element>(r#"<my-widget>Hi</my-gadget>"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<my-widget>Hi</my>"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<a aria - label="x" />"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn markup_attribute_values() {
+        let e: Element = syn::parse_str(r#"
+            <Card
+                icon=<Icon.Cross />
+                header=<Title>Delete <T mono>system32</T>?</Title>
+                footer=<><Button>Cancel</Button><Button>Delete</Button></>
+                primary
+            >
+                Body
+            </Card>
+        "#).expect("Valid parse");
+
+        let Element::Closed(e) = e else { panic!("Expected closed element") };
+        let values: Vec<_> = e.opening.attributes
+            .iter()
+            .map(|a| match a {
+                Attribute::Named(NamedAttribute { initializer: Some(i), .. }) => Some(&i.value),
+                _ => None,
+            })
+            .collect();
+
+        assert!(matches!(values[0], Some(AttributeValue::Element(e)) if matches!(**e, Element::SelfClosing(_))));
+        assert!(matches!(values[1], Some(AttributeValue::Element(e)) if matches!(**e, Element::Closed(_))));
+        assert!(matches!(values[2], Some(AttributeValue::Fragment(_))));
+        assert!(values[3].is_none());
+
+        let _: Element = syn::parse_str(r#"<Icon.Button icon=<Icon.Cross /> />"#).expect("Valid parse");
+        syn::parse_str::<Element>(r#"<Card header=<Title>Oops</Card>"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn text_whitespace() {
+        let e: Element = syn::parse_str(r#"<Button>
+                Clicked {count} times,
+                for $1 000.00 -- or system32?   <Br/>
+            Done!</Button>"#).expect("Valid parse");
+
+        let Element::Closed(e) = e else { panic!("Expected closed element") };
+        let texts: Vec<_> = e.children
+            .iter()
+            .filter_map(|c| match c {
+                Child::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(texts[0].raw, "\n                Clicked ");
+        assert_eq!(texts[0].value().as_deref(), Some("Clicked "));
+
+        assert_eq!(texts[1].raw, " times,\n                for $1 000.00 -- or system32?   ");
+        assert_eq!(texts[1].value().as_deref(), Some(" times, for $1 000.00 -- or system32?   "));
+
+        assert_eq!(texts[2].raw, "\n            Done!");
+        assert_eq!(texts[2].value().as_deref(), Some("Done!"));
+
+        let f: Fragment = syn::parse_str(r#"<>  Hello  world  </>"#).expect("Valid parse");
+        let Child::Text(ref text) = f.children[0] else { panic!("Expected text") };
+        assert_eq!(text.value().as_deref(), Some("  Hello  world  "));
+    }
+
+    #[test]
+    fn text_entities() {
+        let e: Element = crate::syntax::lexer::parse_str("<T>\n    Tom &amp; Jerry&nbsp;&mdash; it's &#169;\n</T>").expect("Valid parse");
+        let Element::Closed(e) = e else { panic!("Expected closed element") };
+        let Child::Text(ref text) = e.children[0] else { panic!("Expected text") };
+
+        assert_eq!(text.raw, "\n    Tom &amp; Jerry&nbsp;&mdash; it's &#169;\n");
+        assert_eq!(text.value().as_deref(), Some("Tom & Jerry\u{a0}\u{2014} it's \u{a9}"));
+
+        let e = crate::syntax::lexer::parse_str::<Element>("<T>\n  Rock &roll;\n</T>").expect_err("Invalid parse");
+        assert_eq!((e[0].span.start().line, e[0].span.start().column), (2, 7));
+        assert_eq!(e[0].span.end().column, 13);
+
+        syn::parse_str::<Element>("<T>Rock &roll;</T>").expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn collapse_whitespace() {
+        use super::collapse_whitespace;
+
+        assert_eq!(collapse_whitespace("Hello world").as_deref(), Some("Hello world"));
+        assert_eq!(collapse_whitespace("\n    Hello\n    world\n").as_deref(), Some("Hello world"));
+        assert_eq!(collapse_whitespace("  a  \r\n\t\n  b  ").as_deref(), Some("  a b  "));
+        assert_eq!(collapse_whitespace("\n    \n  ").as_deref(), None);
+        assert_eq!(collapse_whitespace("   ").as_deref(), Some("   "));
+    }
+
     #[test]
     fn element_path_test() {
         let _: Element = syn::parse_str(r#"<icon::Cactus color="green" />"#).expect("Valid parse");
@@ -0,0 +1,673 @@
+//!
+//! Lexer for `.pony` source.
+//!
+//! Markup text and the insides of comments are scanned as raw characters,
+//! so they may contain anything (`Don't panic`, `#`, `\`, a lone `"`),
+//! whereas everything else -- tags, `{...}`, attribute expressions and `<script>` --
+//! is still tokenized as Rust. The insides of `<style>` are raw too.
+//!
+//! Each run of raw text is handed to the parsers as a string literal,
+//! wrapped in a [`Delimiter::None`] group, spanning where it was in the source.
+//!
+
+use std::{collections::HashMap, ops::Range, str::FromStr};
+
+use proc_macro2::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::parser;
+
+///
+/// Tokenizes `.pony` source.
+///
+pub fn lex(source: &str) -> Result<TokenStream> {
+    let mut lexer = Lexer {
+        chars: source.chars().collect(),
+        i: 0,
+        regions: vec![],
+    };
+
+    lexer.content(false)?;
+    lexer.tokenize()
+}
+
+///
+/// Same as [`parser::parse_str`], but for `.pony` source.
+///
+pub fn parse_str<T: parser::Parse>(source: &str) -> Result<T> {
+    parser::parse2(lex(source)?)
+}
+
+///
+/// The raw text `tokens` stand for, if they came from the lexer.
+///
+pub(crate) fn raw_text(tokens: &TokenStream) -> Option<String> {
+    let mut iter = tokens.clone().into_iter();
+
+    let (Some(TokenTree::Group(group)), None) = (iter.next(), iter.next()) else {
+        return None;
+    };
+
+    if group.delimiter() != Delimiter::None {
+        return None;
+    }
+
+    let mut iter = group.stream().into_iter();
+    let (Some(TokenTree::Literal(lit)), None) = (iter.next(), iter.next()) else {
+        return None;
+    };
+
+    match syn::Lit::new(lit) {
+        syn::Lit::Str(lit) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+///
+/// Span of `chars` within the raw text `tokens` stand for, if they came from the lexer.
+///
+pub(crate) fn raw_text_span(tokens: &TokenStream, chars: Range<usize>) -> Option<Span> {
+    let raw = raw_text(tokens)?;
+    let TokenTree::Group(group) = tokens.clone().into_iter().next()? else {
+        return None;
+    };
+    let TokenTree::Literal(lit) = group.stream().into_iter().next()? else {
+        return None;
+    };
+
+    // The literal only spans from the first non-whitespace character.
+    let leading = raw.chars().take_while(|c| c.is_whitespace()).count();
+    lit.subspan(chars.start.checked_sub(leading)?..chars.end.checked_sub(leading)?)
+}
+
+enum TagKind {
+    Opening,
+    Closing,
+    SelfClosing,
+    Script,
+    Style,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    i: usize,
+    ///
+    /// Raw text (and comment contents) found so far.
+    ///
+    regions: Vec<Range<usize>>,
+}
+
+impl Lexer {
+    fn at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.i + offset).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(n, c)| self.at(n) == Some(c))
+    }
+
+    fn is_ident_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    ///
+    /// Markup: text, comments, tags and `{...}`.
+    ///
+    /// If `nested`, this stops after the element starting here is closed,
+    /// for elements used as attribute values.
+    ///
+    fn content(&mut self, nested: bool) -> Result<()> {
+        let mut depth = 0_isize;
+        let mut text = self.i;
+
+        while let Some(c) = self.at(0) {
+            if c == '<' && self.starts_with("<!--") {
+                self.text(text..self.i);
+                self.comment()?;
+            } else if c == '<' && self.at(1).is_some_and(|c| matches!(c, '/' | '>' | ':') || c.is_alphabetic() || c == '_') {
+                self.text(text..self.i);
+
+                match self.tag()? {
+                    TagKind::Opening => depth += 1,
+                    TagKind::Closing => depth -= 1,
+                    TagKind::SelfClosing => {}
+                    TagKind::Script => {
+                        depth += 1;
+                        self.script()?;
+                    }
+                    TagKind::Style => {
+                        depth += 1;
+                        self.style()?;
+                    }
+                }
+
+                if nested && depth <= 0 {
+                    return Ok(());
+                }
+            } else if c == '{' {
+                self.text(text..self.i);
+                self.rust_group()?;
+            } else if matches!(c, '<' | '>' | '}') {
+                // Not allowed in text: left as tokens, for the parser to complain about.
+                self.text(text..self.i);
+                self.i += 1;
+            } else {
+                self.i += 1;
+                continue;
+            }
+
+            text = self.i;
+        }
+
+        self.text(text..self.i);
+        Ok(())
+    }
+
+    ///
+    /// Records a run of text, unless it is only whitespace
+    /// that JSX would remove anyway.
+    ///
+    fn text(&mut self, range: Range<usize>) {
+        let chars = &self.chars[range.clone()];
+
+        if chars.is_empty() {
+            return;
+        }
+
+        if chars.iter().all(|c| c.is_whitespace())
+            && (chars.contains(&'\n') || range.start == 0 || range.end == self.chars.len())
+        {
+            return;
+        }
+
+        self.regions.push(range);
+    }
+
+    ///
+    /// `<!-- ... -->`
+    ///
+    fn comment(&mut self) -> Result<()> {
+        let start = self.i;
+        self.i += 4;
+
+        while !self.starts_with("-->") {
+            if self.at(0).is_none() {
+                return Err(self.error(start, "This comment is never closed: expected `-->`").into());
+            }
+
+            self.i += 1;
+        }
+
+        let contents = &self.chars[start + 4..self.i];
+        if !contents.iter().all(|c| c.is_whitespace()) {
+            self.regions.push(start + 4..self.i);
+        }
+
+        self.i += 3;
+        Ok(())
+    }
+
+    ///
+    /// `<...>`, `</...>` or `<.../>`, including any attributes.
+    ///
+    fn tag(&mut self) -> Result<TagKind> {
+        let start = self.i;
+        self.i += 1;
+
+        let closing = self.at(0) == Some('/');
+        if closing {
+            self.i += 1;
+        }
+
+        let name = |name: &str| self.starts_with(name) && !self.at(name.len()).is_some_and(|c| Self::is_ident_char(c) || c == '-');
+        let (script, style) = (name("script"), name("style"));
+
+        // Generic arguments in element names, as in `<List<Item>>`.
+        let mut angles = 0;
+
+        loop {
+            let Some(c) = self.at(0) else {
+                return Err(self.error(start, "This tag is never closed: expected `>`").into());
+            };
+
+            match c {
+                '"' => self.string()?,
+                'r' if self.raw_string_start() => self.string()?,
+                '{' => self.rust_group()?,
+                '<' if self.follows('=') => self.content(true)?,
+                '<' => {
+                    angles += 1;
+                    self.i += 1;
+                }
+                '-' if self.at(1) == Some('>') => self.i += 2,
+                '>' if angles > 0 => {
+                    angles -= 1;
+                    self.i += 1;
+                }
+                '/' if self.at(1) == Some('>') => {
+                    self.i += 2;
+                    return Ok(TagKind::SelfClosing);
+                }
+                '>' => {
+                    self.i += 1;
+
+                    return Ok(match (closing, script, style) {
+                        (true, _, _) => TagKind::Closing,
+                        (false, true, _) => TagKind::Script,
+                        (false, _, true) => TagKind::Style,
+                        (false, false, false) => TagKind::Opening,
+                    });
+                }
+                _ => self.i += 1,
+            }
+        }
+    }
+
+    ///
+    /// Whether the last non-whitespace character before here is `c`.
+    ///
+    fn follows(&self, c: char) -> bool {
+        self.chars[..self.i].iter().rev().find(|c| !c.is_whitespace()) == Some(&c)
+    }
+
+    ///
+    /// The Rust inside `<script>`, up to (not including) `</script>`.
+    ///
+    fn script(&mut self) -> Result<()> {
+        let start = self.i;
+
+        while !self.starts_with("</script") {
+            if self.at(0).is_none() {
+                return Err(self.error(start, "This `<script>` is never closed: expected `</script>`").into());
+            }
+
+            self.rust_token()?;
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// The raw CSS inside `<style>`, up to (not including) `</style>`.
+    ///
+    fn style(&mut self) -> Result<()> {
+        let start = self.i;
+
+        while !self.starts_with("</style") {
+            if self.at(0).is_none() {
+                return Err(self.error(start, "This `<style>` is never closed: expected `</style>`").into());
+            }
+
+            self.i += 1;
+        }
+
+        if !self.chars[start..self.i].iter().all(|c| c.is_whitespace()) {
+            self.regions.push(start..self.i);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// A brace-delimited group of Rust.
+    ///
+    fn rust_group(&mut self) -> Result<()> {
+        let start = self.i;
+        let mut depth = 0;
+
+        loop {
+            match self.at(0) {
+                None => return Err(self.error(start, "This `{` is never closed: expected `}`").into()),
+                Some('{') => depth += 1,
+                Some('}') => depth -= 1,
+                _ => {}
+            }
+
+            self.rust_token()?;
+
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    ///
+    /// Skips over a character of Rust, or a whole literal or comment,
+    /// so that braces and `</script` within them are ignored.
+    ///
+    fn rust_token(&mut self) -> Result<()> {
+        match self.at(0) {
+            Some('"') => self.string(),
+            Some('r') if self.raw_string_start() => self.string(),
+            Some('\'') => {
+                // Character literal, otherwise a lifetime.
+                match (self.at(1), self.at(2)) {
+                    (Some('\\'), _) => {
+                        let start = self.i;
+                        self.i += 2;
+
+                        while self.at(0) != Some('\'') {
+                            if self.at(0).is_none() {
+                                return Err(self.error(start, "This character literal is never closed").into());
+                            }
+
+                            self.i += 1;
+                        }
+
+                        self.i += 1;
+                    }
+                    (Some(_), Some('\'')) => self.i += 3,
+                    _ => self.i += 1,
+                }
+
+                Ok(())
+            }
+            Some('/') if self.at(1) == Some('/') => {
+                while self.at(0).is_some_and(|c| c != '\n') {
+                    self.i += 1;
+                }
+
+                Ok(())
+            }
+            Some('/') if self.at(1) == Some('*') => {
+                let start = self.i;
+                let mut depth = 0;
+
+                loop {
+                    if self.starts_with("/*") {
+                        depth += 1;
+                        self.i += 2;
+                    } else if self.starts_with("*/") {
+                        depth -= 1;
+                        self.i += 2;
+
+                        if depth == 0 {
+                            return Ok(());
+                        }
+                    } else if self.at(0).is_none() {
+                        return Err(self.error(start, "This block comment is never closed").into());
+                    } else {
+                        self.i += 1;
+                    }
+                }
+            }
+            _ => {
+                self.i += 1;
+                Ok(())
+            }
+        }
+    }
+
+    ///
+    /// Whether an `r` here starts a raw string: `r"..."`, `r#"..."#` or `br"..."`.
+    ///
+    fn raw_string_start(&self) -> bool {
+        let before = |n: usize| self.i.checked_sub(n).map(|i| self.chars[i]);
+
+        let prefix = match before(1) {
+            Some('b') => !before(2).is_some_and(Self::is_ident_char),
+            Some(c) => !Self::is_ident_char(c),
+            None => true,
+        };
+
+        let hashes = (1..).take_while(|&n| self.at(n) == Some('#')).count();
+        prefix && self.at(1 + hashes) == Some('"')
+    }
+
+    ///
+    /// A (possibly raw) string literal.
+    ///
+    fn string(&mut self) -> Result<()> {
+        let start = self.i;
+
+        if self.at(0) == Some('r') {
+            self.i += 1;
+
+            let hashes = (0..).take_while(|&n| self.at(n) == Some('#')).count();
+            let closing = format!("\"{}", "#".repeat(hashes));
+            self.i += hashes + 1;
+
+            while !self.starts_with(&closing) {
+                if self.at(0).is_none() {
+                    return Err(self.error(start, "This string is never closed").into());
+                }
+
+                self.i += 1;
+            }
+
+            self.i += closing.len();
+            return Ok(());
+        }
+
+        self.i += 1;
+
+        loop {
+            match self.at(0) {
+                None => return Err(self.error(start, "This string is never closed").into()),
+                Some('\\') => self.i += 2,
+                Some('"') => {
+                    self.i += 1;
+                    return Ok(());
+                }
+                Some(_) => self.i += 1,
+            }
+        }
+    }
+
+    ///
+    /// Tokenizes the source with each region of raw text blanked out by
+    /// a placeholder literal of the same length, so every other token keeps its place,
+    /// then swaps the placeholders for the raw text.
+    ///
+    fn tokenize(&self) -> Result<TokenStream> {
+        let mut skeleton = self.chars.clone();
+        let mut raw = HashMap::new();
+
+        for region in &self.regions {
+            let chars = &self.chars[region.clone()];
+
+            // Only over the non-whitespace, so the whitespace is left for
+            // the Rust tokenizer, unless there is nothing else.
+            let placeholder = match (
+                chars.iter().position(|c| !c.is_whitespace()),
+                chars.iter().rposition(|c| !c.is_whitespace()),
+            ) {
+                (Some(first), Some(last)) => region.start + first..region.start + last + 1,
+                _ => region.clone(),
+            };
+
+            for c in &mut skeleton[placeholder.clone()] {
+                if *c != '\n' {
+                    *c = ' ';
+                }
+            }
+
+            if placeholder.len() == 1 {
+                skeleton[placeholder.start] = '0';
+            } else {
+                skeleton[placeholder.start] = '"';
+                skeleton[placeholder.end - 1] = '"';
+            }
+
+            raw.insert(self.line_column(placeholder.start), chars.iter().collect::<String>());
+        }
+
+        let skeleton: String = skeleton.into_iter().collect();
+        let tokens = TokenStream::from_str(&skeleton).map_err(|e| Diagnostic::new(Code::Syntax, e.span(), e))?;
+
+        Ok(replace_placeholders(tokens, &raw))
+    }
+
+    ///
+    /// (1-indexed line, 0-indexed column), as in [`proc_macro2::LineColumn`].
+    ///
+    fn line_column(&self, index: usize) -> (usize, usize) {
+        let before = &self.chars[..index];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count();
+
+        (line, column)
+    }
+
+    ///
+    /// An error at the character `index`.
+    ///
+    fn error(&self, index: usize, message: &str) -> Diagnostic {
+        // Spans can only come from tokenizing, so tokenize something
+        // with a token at the same line and column.
+        let (line, column) = self.line_column(index);
+        let at = format!("{}{}_", "\n".repeat(line - 1), " ".repeat(column));
+
+        let span = TokenStream::from_str(&at)
+            .ok()
+            .and_then(|tokens| tokens.into_iter().next())
+            .map_or_else(Span::call_site, |tt| tt.span());
+
+        Diagnostic::new(Code::Unterminated, span, message)
+    }
+}
+
+fn replace_placeholders(tokens: TokenStream, raw: &HashMap<(usize, usize), String>) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(group) => {
+                let mut new = Group::new(group.delimiter(), replace_placeholders(group.stream(), raw));
+                new.set_span(group.span());
+                TokenTree::Group(new)
+            }
+            TokenTree::Literal(lit) => {
+                let start = lit.span().start();
+
+                match raw.get(&(start.line, start.column)) {
+                    Some(text) => {
+                        let mut text = Literal::string(text);
+                        text.set_span(lit.span());
+
+                        let mut group = Group::new(Delimiter::None, TokenTree::Literal(text).into());
+                        group.set_span(lit.span());
+                        TokenTree::Group(group)
+                    }
+                    None => TokenTree::Literal(lit),
+                }
+            }
+            tt => tt,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::jsx::{Child, Element, Root};
+
+    use super::parse_str;
+
+    fn children(source: &str) -> Vec<Child> {
+        let Element::Closed(e) = parse_str(source).expect("Valid parse") else {
+            panic!("Expected closed element")
+        };
+
+        e.children
+    }
+
+    #[test]
+    fn raw_text() {
+        let c = children(r#"<T>Don't panic</T>"#);
+        assert!(matches!(c[0], Child::Text(ref t) if t.raw == "Don't panic"));
+
+        let c = children(r#"<T>A lone " and a \ and # too // not a comment</T>"#);
+        assert!(matches!(c[0], Child::Text(ref t) if t.raw == r#"A lone " and a \ and # too // not a comment"#));
+
+        let c = children("<T>\n    Ferris says: \"Rewrite it in Rust!\" 🦀\n</T>");
+        assert!(matches!(c[0], Child::Text(ref t) if t.value().as_deref() == Some("Ferris says: \"Rewrite it in Rust!\" 🦀")));
+
+        let c = children(r#"<T>I'm {name}, I'm {age}</T>"#);
+        assert_eq!(c.len(), 4);
+        assert!(matches!(c[2], Child::Text(ref t) if t.raw == ", I'm "));
+
+        let c = children(r#"<T>x</T>"#);
+        assert!(matches!(c[0], Child::Text(ref t) if t.raw == "x"));
+    }
+
+    #[test]
+    fn whitespace() {
+        let c = children("<P>\n    <A />\n    <B />\n</P>");
+        assert_eq!(c.len(), 2);
+
+        let c = children(r#"<P>{first} {last}</P>"#);
+        assert_eq!(c.len(), 3);
+        assert!(matches!(c[1], Child::Text(ref t) if t.raw == " " && t.value().as_deref() == Some(" ")));
+
+        let c = children(r#"<P>  <A />  </P>"#);
+        assert_eq!(c.len(), 3);
+
+        parse_str::<Root>("  \n  <A />  \n").expect("Valid parse");
+        parse_str::<Root>(" <A /> ").expect("Valid parse");
+    }
+
+    #[test]
+    fn comments() {
+        let c = children(r#"<T><!-- it's "quoted" --></T>"#);
+        assert!(matches!(c[0], Child::Comment(ref c) if c.raw == r#" it's "quoted" "#));
+
+        let c = children(r#"<T><!----></T>"#);
+        assert!(matches!(c[0], Child::Comment(ref c) if c.raw.is_empty()));
+
+        let e = parse_str::<Root>(r#"<T><!-- never closed </T>"#).expect_err("Invalid parse");
+        assert_eq!(e[0].span.start().column, 3);
+    }
+
+    #[test]
+    fn rust_is_still_rust() {
+        let c = children(r#"<T on:click={|| { println!("}}>'{"); 'a' }}>Don't</T>"#);
+        assert!(matches!(c[0], Child::Text(ref t) if t.raw == "Don't"));
+
+        let c = children(r#"<T>{#if project.language != "Rust"}Rewrite it, y'all!{:else}Proud{/if}</T>"#);
+        assert!(matches!(c[0], Child::If(_)));
+
+        let c = children(r#"<T>{#match role} <!-- Who's there? --> {:case _}Nobody{/match}</T>"#);
+        assert!(matches!(c[0], Child::Match(ref m) if m.comments.len() == 1));
+
+        let c = children(r#"<Card header=<Title>Don't</Title> footer={"}"}>It's fine</Card>"#);
+        assert!(matches!(c[0], Child::Text(ref t) if t.raw == "It's fine"));
+
+        let c = children(r#"<List<Item> data-x="y">Items'</List<Item>>"#);
+        assert_eq!(c.len(), 1);
+
+        let e = parse_str::<Root>(r#"<T on:click={|| {}>Oops</T>"#).expect_err("Invalid parse");
+        assert!(e.to_string().contains("never closed"));
+    }
+
+    #[test]
+    fn spans() {
+        let e = parse_str::<Root>("<T>\n  It's {}\n</T>").expect_err("Invalid parse");
+        assert_eq!(e[0].span.start().line, 2);
+
+        let e = parse_str::<Root>("<T>\n  It's >\n</T>").expect_err("Invalid parse");
+        assert_eq!((e[0].span.start().line, e[0].span.start().column), (2, 7));
+    }
+
+    #[test]
+    fn script() {
+        let source = r#"<script>
+            // Don't mind me: </Closing> {
+            let s = "</script>";
+        </script>"#;
+
+        let tokens = super::lex(source).expect("Valid lex");
+        assert!(tokens.to_string().contains("\"</script>\""));
+    }
+
+    #[test]
+    fn style() {
+        let source = "<style>\n    p::after { content: 'Don\\'t'; color: #fff }\n</style>";
+
+        let tokens = super::lex(source).expect("Valid lex");
+        let mut iter = tokens.into_iter().skip(3);
+        let raw = super::raw_text(&iter.next().expect("Style contents").into());
+        assert_eq!(raw.as_deref(), Some("\n    p::after { content: 'Don\\'t'; color: #fff }\n"));
+
+        let e = super::lex("<style> p { }").expect_err("Invalid lex");
+        assert!(e.to_string().contains("never closed"));
+    }
+}
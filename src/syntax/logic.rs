@@ -0,0 +1,1199 @@
+//!
+//! Svelte-style logic blocks: `{#if ...}`, `{#for ...}`, etc.
+//!
+
+use std::fmt::Debug;
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::{ToTokens, TokenStreamExt};
+use syn::{
+    buffer::Cursor,
+    parse::{ParseStream, Parser},
+    spanned::Spanned,
+    visit_mut::VisitMut,
+    Token,
+};
+
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::jsx::{parse_child, Child, Children, Comment, Text};
+use super::lexer;
+use super::parser::{self, Context, Parse as _};
+
+pub mod kw {
+    syn::custom_keyword!(case);
+    syn::custom_keyword!(catch);
+    syn::custom_keyword!(key);
+}
+
+///
+/// Looks inside the next brace group (if any) for a block sigil:
+/// * `#` opens a block (`{#if ...}`),
+/// * `:` continues a block (`{:else}`),
+/// * `/` closes a block (`{/if}`),
+/// * `@` starts a tag (`{@let ...}`).
+///
+/// Returns the sigil with the keyword that follows it, if there is one.
+///
+pub(crate) fn peek_block(input: ParseStream) -> Option<(char, String)> {
+    block_at(input.cursor())
+}
+
+///
+/// Same as [`peek_block`], at `cursor`.
+///
+pub(crate) fn block_at(cursor: Cursor) -> Option<(char, String)> {
+    let (inner, _, _) = cursor.group(Delimiter::Brace)?;
+    let (punct, rest) = inner.punct()?;
+
+    match punct.as_char() {
+        // `{::std::mem::take(x)}` is a mustache, `{:else}` is not.
+        ':' if punct.spacing() == Spacing::Joint => None,
+        c @ ('#' | ':' | '/' | '@') => Some((
+            c,
+            rest.ident()
+                .map(|(ident, _)| ident.to_string())
+                .unwrap_or_default(),
+        )),
+        _ => None,
+    }
+}
+
+///
+/// Children of a logic block: anything up until the next `{:...}` or `{/...}`,
+/// or the closing tag of an enclosing element.
+///
+fn parse_block_children(input: ParseStream, cx: &mut Context, brace: syn::token::Brace) -> Result<Children> {
+    let mut children = vec![];
+    let mut prev = Some(brace.span.close());
+
+    while !(input.is_empty()
+        || matches!(peek_block(input), Some((':' | '/', _)))
+        || (input.peek(Token![<]) && input.peek2(Token![/])))
+    {
+        children.push(parse_child(input, cx, &mut prev)?);
+    }
+
+    Ok(children)
+}
+
+///
+/// Whether the next token is whitespace-only text.
+///
+fn is_blank(input: ParseStream) -> bool {
+    input.cursor().token_tree().is_some_and(|(tt, _)| {
+        lexer::raw_text(&tt.into()).is_some_and(|raw| raw.trim().is_empty())
+    })
+}
+
+fn unclosed(brace: &syn::token::Brace, keyword: &str) -> Diagnostic {
+    Diagnostic::new(
+        Code::UnclosedBlock,
+        brace.span.join(),
+        format!("`{{#{keyword}}}` block is never closed: expected `{{/{keyword}}}`"),
+    )
+}
+
+///
+/// Errors out on anything but `{/keyword}`, reporting which block is left open.
+///
+fn expect_closing(input: ParseStream, brace: &syn::token::Brace, keyword: &str) -> Result<()> {
+    match peek_block(input) {
+        Some(('/', ref k)) if k == keyword => Ok(()),
+        Some(('/', k)) => Err(Diagnostic::at(
+            input,
+            Code::MismatchedBlockClosing,
+            format!("Expected `{{/{keyword}}}` here, found `{{/{k}}}`"),
+        )
+        .label(brace.span.join(), format!("`{{#{keyword}}}` opened here"))
+        .suggest(input.span(), "Close the innermost block", format!("{{/{keyword}}}"))
+        .into()),
+        Some((':', k)) => Err(Diagnostic::at(
+            input,
+            Code::MisplacedBranch,
+            format!("Unexpected `{{:{k}}}` in `{{#{keyword}}}` block"),
+        )
+        .label(brace.span.join(), format!("`{{#{keyword}}}` opened here"))
+        .into()),
+        _ if input.is_empty() => Err(unclosed(brace, keyword).into()),
+        _ => Err(Diagnostic::at(
+            input,
+            Code::UnclosedBlock,
+            format!("Expected `{{/{keyword}}}` to close `{{#{keyword}}}` block before this"),
+        )
+        .label(brace.span.join(), format!("`{{#{keyword}}}` opened here"))
+        .suggest(input.span(), "Close it", format!("{{/{keyword}}}"))
+        .into()),
+    }
+}
+
+///
+/// Closing tag of a block, such as `{/if}`.
+///
+pub struct BlockClosing<K> {
+    pub brace: syn::token::Brace,
+    pub slash: Token![/],
+    pub keyword: K,
+}
+
+impl<K: syn::parse::Parse> syn::parse::Parse for BlockClosing<K> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let inner;
+        let brace = syn::braced!(inner in input);
+        Ok(Self {
+            brace,
+            slash: inner.parse()?,
+            keyword: inner.parse()?,
+        })
+    }
+}
+
+///
+/// Either a plain boolean expression (`{#if a == b}`),
+/// or a pattern match (`{#if let Some(ref x) = y}`).
+///
+pub enum Condition {
+    Let(syn::ExprLet),
+    Expr(syn::Expr),
+}
+
+impl syn::parse::Parse for Condition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![let]) {
+            return Ok(Self::Let(input.parse()?));
+        }
+
+        Ok(Self::Expr(input.parse()?))
+    }
+}
+
+impl Debug for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Let(expr) => write!(f, "{}", expr.to_token_stream()),
+            Self::Expr(expr) => write!(f, "{}", expr.to_token_stream()),
+        }
+    }
+}
+
+///
+/// ```svelte
+/// {#if let Some(ref chicken) = self.fridge.get("chicken")}
+///     I have {chicken.weight} kgs of chicken.
+/// {:else if self.fridge.is_empty()}
+///     Nothing at all?!
+/// {:else}
+///     No chicken?!
+/// {/if}
+/// ```
+///
+pub struct If {
+    pub brace: syn::token::Brace,
+    pub pound: Token![#],
+    pub if_token: Token![if],
+    pub condition: Condition,
+    pub children: Children,
+    pub else_ifs: Vec<ElseIf>,
+    pub otherwise: Option<Else>,
+    pub closing: BlockClosing<Token![if]>,
+}
+
+impl syn::parse::Parse for If {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for If {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        let pound = inner.parse()?;
+        let if_token = inner.parse()?;
+        let condition = inner.parse()?;
+        let children = parse_block_children(input, cx, brace)?;
+
+        let mut else_ifs = vec![];
+        let mut otherwise: Option<Else> = None;
+
+        loop {
+            match peek_block(input) {
+                Some((':', ref k)) if k == "else" => {
+                    if let Some(ref otherwise) = otherwise {
+                        return Err(Diagnostic::at(
+                            input,
+                            Code::MisplacedBranch,
+                            "`{:else}` must be the last branch of an `{#if}` block",
+                        )
+                        .label(otherwise.brace.span.join(), "The last branch is already here")
+                        .into());
+                    }
+
+                    if ElseIf::peek(input) {
+                        else_ifs.push(ElseIf::parse(input, cx)?);
+                    } else {
+                        otherwise = Some(Else::parse(input, cx)?);
+                    }
+                }
+                _ => {
+                    expect_closing(input, &brace, "if")?;
+                    break;
+                }
+            }
+        }
+
+        Ok(Self {
+            brace,
+            pound,
+            if_token,
+            condition,
+            children,
+            else_ifs,
+            otherwise,
+            closing: input.parse()?,
+        })
+    }
+}
+
+impl Debug for If {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("If");
+
+        s.field("condition", &self.condition)
+            .field("children", &self.children);
+
+        for else_if in &self.else_ifs {
+            s.field("else_if", else_if);
+        }
+
+        if let Some(ref otherwise) = self.otherwise {
+            s.field("else", otherwise);
+        }
+
+        s.finish()
+    }
+}
+
+///
+/// `{:else if condition}` branch of an [`If`] block.
+///
+pub struct ElseIf {
+    pub brace: syn::token::Brace,
+    pub colon: Token![:],
+    pub else_token: Token![else],
+    pub if_token: Token![if],
+    pub condition: Condition,
+    pub children: Children,
+}
+
+impl ElseIf {
+    fn peek(input: ParseStream) -> bool {
+        let Some((inner, _, _)) = input.cursor().group(Delimiter::Brace) else {
+            return false;
+        };
+
+        inner
+            .punct()
+            .and_then(|(_, rest)| rest.ident())
+            .and_then(|(_, rest)| rest.ident())
+            .is_some_and(|(i, _)| i == "if")
+    }
+}
+
+impl parser::Parse for ElseIf {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        Ok(Self {
+            brace,
+            colon: inner.parse()?,
+            else_token: inner.parse()?,
+            if_token: inner.parse()?,
+            condition: inner.parse()?,
+            children: parse_block_children(input, cx, brace)?,
+        })
+    }
+}
+
+impl Debug for ElseIf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ElseIf")
+            .field("condition", &self.condition)
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+///
+/// `{:else}` branch of a block.
+///
+pub struct Else {
+    pub brace: syn::token::Brace,
+    pub colon: Token![:],
+    pub else_token: Token![else],
+    pub children: Children,
+}
+
+impl parser::Parse for Else {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        Ok(Self {
+            brace,
+            colon: inner.parse()?,
+            else_token: inner.parse()?,
+            children: parse_block_children(input, cx, brace)?,
+        })
+    }
+}
+
+impl Debug for Else {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.children.fmt(f)
+    }
+}
+
+///
+/// ```svelte
+/// {#for (fruit, count) in basket.iter()}
+///     <Icon id={fruit} /> {fruit} count: {count} <Br />
+/// {:else}
+///     The basket is empty.
+/// {/for}
+/// ```
+///
+/// The `{:else}` branch is rendered when the iterator yields nothing.
+///
+pub struct For {
+    pub brace: syn::token::Brace,
+    pub pound: Token![#],
+    pub for_token: Token![for],
+    pub pat: syn::Pat,
+    pub in_token: Token![in],
+    pub iter: syn::Expr,
+    pub children: Children,
+    pub otherwise: Option<Else>,
+    pub closing: BlockClosing<Token![for]>,
+}
+
+impl syn::parse::Parse for For {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for For {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        let pound = inner.parse()?;
+        let for_token = inner.parse()?;
+        let pat = syn::Pat::parse_multi_with_leading_vert(&inner)?;
+        let in_token = inner.parse()?;
+        let iter = inner.parse()?;
+        let children = parse_block_children(input, cx, brace)?;
+
+        let otherwise = match peek_block(input) {
+            Some((':', ref k)) if k == "else" => Some(Else::parse(input, cx)?),
+            _ => None,
+        };
+
+        expect_closing(input, &brace, "for")?;
+
+        Ok(Self {
+            brace,
+            pound,
+            for_token,
+            pat,
+            in_token,
+            iter,
+            children,
+            otherwise,
+            closing: input.parse()?,
+        })
+    }
+}
+
+impl Debug for For {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("For");
+
+        s.field("pat", &self.pat.to_token_stream().to_string())
+            .field("iter", &self.iter.to_token_stream().to_string())
+            .field("children", &self.children);
+
+        if let Some(ref otherwise) = self.otherwise {
+            s.field("else", otherwise);
+        }
+
+        s.finish()
+    }
+}
+
+///
+/// ```svelte
+/// {#match user.role()}
+///     <!-- Comments are the only thing allowed before the first case. -->
+///     {:case Role::Root}
+///         <T>With great power comes great responsibility.</T>
+///     {:case _}
+///         <T>{user.username} is not in the sudoers file.</T>
+/// {/match}
+/// ```
+///
+pub struct Match {
+    pub brace: syn::token::Brace,
+    pub pound: Token![#],
+    pub match_token: Token![match],
+    pub expr: syn::Expr,
+    pub comments: Vec<Comment>,
+    pub arms: Vec<Case>,
+    pub closing: BlockClosing<Token![match]>,
+}
+
+impl syn::parse::Parse for Match {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Match {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        let pound = inner.parse()?;
+        let match_token = inner.parse()?;
+        let expr = inner.parse()?;
+
+        let mut comments = vec![];
+        loop {
+            if Comment::peek(input) {
+                comments.push(input.parse()?);
+            } else if is_blank(input) {
+                // Spaces between blocks on one line, from the lexer.
+                let _ = Text::parse(input, cx)?;
+            } else {
+                break;
+            }
+        }
+
+        let mut arms = vec![];
+        while matches!(peek_block(input), Some((':', ref k)) if k == "case") {
+            arms.push(Case::parse(input, cx)?);
+        }
+
+        if arms.is_empty() && !(input.is_empty() || peek_block(input).is_some()) {
+            return Err(Diagnostic::at(
+                input,
+                Code::MisplacedBranch,
+                "Only comments may appear between `{#match ...}` and the first `{:case ...}`",
+            )
+            .label(brace.span.join(), "`{#match}` opened here")
+            .into());
+        }
+
+        expect_closing(input, &brace, "match")?;
+
+        Ok(Self {
+            brace,
+            pound,
+            match_token,
+            expr,
+            comments,
+            arms,
+            closing: input.parse()?,
+        })
+    }
+}
+
+impl Debug for Match {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Match")
+            .field("expr", &self.expr.to_token_stream().to_string())
+            .field("arms", &self.arms)
+            .finish()
+    }
+}
+
+///
+/// `{:case pattern if guard}` arm of a [`Match`] block.
+///
+pub struct Case {
+    pub brace: syn::token::Brace,
+    pub colon: Token![:],
+    pub case_token: kw::case,
+    pub pat: syn::Pat,
+    pub guard: Option<(Token![if], Box<syn::Expr>)>,
+    pub children: Children,
+}
+
+impl parser::Parse for Case {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        Ok(Self {
+            brace,
+            colon: inner.parse()?,
+            case_token: inner.parse()?,
+            pat: parse_case_pat(&inner)?,
+            guard: if inner.peek(Token![if]) {
+                Some((inner.parse()?, inner.parse()?))
+            } else {
+                None
+            },
+            children: parse_block_children(input, cx, brace)?,
+        })
+    }
+}
+
+impl Debug for Case {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("Case");
+
+        s.field("pat", &self.pat.to_token_stream().to_string());
+
+        if let Some((_, ref guard)) = self.guard {
+            s.field("guard", &guard.to_token_stream().to_string());
+        }
+
+        s.field("children", &self.children).finish()
+    }
+}
+
+///
+/// Unlike Rust, we allow arbitrary expressions as range pattern bounds
+/// (`age_rating @ ..=user.age()`).
+///
+/// `syn` only accepts literals, paths and `const { ... }` blocks there,
+/// so wrap any other bound in a `const` block, parse, then unwrap it again.
+///
+fn parse_case_pat(input: ParseStream) -> syn::Result<syn::Pat> {
+    let mut tokens = TokenStream::new();
+    while !(input.is_empty() || input.peek(Token![if])) {
+        tokens.append(input.parse::<TokenTree>()?);
+    }
+
+    let mut pat = syn::Pat::parse_multi_with_leading_vert.parse2(wrap_range_bounds(tokens))?;
+    UnwrapRangeBounds.visit_pat_mut(&mut pat);
+
+    Ok(pat)
+}
+
+fn wrap_range_bounds(tokens: TokenStream) -> TokenStream {
+    let mut out = TokenStream::new();
+    let mut iter = tokens.into_iter().peekable();
+
+    while let Some(tt) = iter.next() {
+        let dot = match tt {
+            TokenTree::Group(ref group) => {
+                let mut wrapped = proc_macro2::Group::new(group.delimiter(), wrap_range_bounds(group.stream()));
+                wrapped.set_span(group.span());
+                out.append(wrapped);
+                continue;
+            }
+            TokenTree::Punct(ref p) if p.as_char() == '.' && p.spacing() == Spacing::Joint => {
+                matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '.')
+            }
+            _ => false,
+        };
+
+        out.append(tt);
+
+        if !dot {
+            continue;
+        }
+
+        // `..` or `..=`
+        let second = iter.next().unwrap();
+        let inclusive = matches!(second, TokenTree::Punct(ref p) if p.spacing() == Spacing::Joint)
+            && matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '=');
+        out.append(second);
+        if inclusive {
+            out.append(iter.next().unwrap());
+        }
+
+        let mut bound = vec![];
+        while let Some(tt) = iter.peek() {
+            match tt {
+                TokenTree::Punct(p) if p.as_char() == ',' || p.as_char() == '|' => break,
+                TokenTree::Ident(i) if i == "if" => break,
+                _ => bound.push(iter.next().unwrap()),
+            }
+        }
+
+        let simple = bound.iter().all(|tt| match tt {
+            TokenTree::Punct(p) => p.as_char() == ':' || p.as_char() == '-',
+            TokenTree::Ident(i) => i != "const",
+            TokenTree::Literal(_) => true,
+            TokenTree::Group(_) => false,
+        });
+
+        if simple || matches!(bound.first(), Some(TokenTree::Ident(i)) if i == "const") {
+            out.extend(bound);
+        } else {
+            let span = bound[0].span();
+            out.append(proc_macro2::Ident::new("const", span));
+            let mut block = proc_macro2::Group::new(Delimiter::Brace, bound.into_iter().collect());
+            block.set_span(span);
+            out.append(block);
+        }
+    }
+
+    out
+}
+
+struct UnwrapRangeBounds;
+
+impl VisitMut for UnwrapRangeBounds {
+    fn visit_pat_mut(&mut self, pat: &mut syn::Pat) {
+        if let syn::Pat::Range(range) = pat {
+            for bound in range.start.iter_mut().chain(range.end.iter_mut()) {
+                let syn::Expr::Const(ref c) = **bound else {
+                    continue;
+                };
+
+                if let [syn::Stmt::Expr(ref expr, None)] = c.block.stmts[..] {
+                    **bound = expr.clone();
+                }
+            }
+        }
+
+        syn::visit_mut::visit_pat_mut(self, pat);
+    }
+}
+
+///
+/// ```svelte
+/// {#async Cookbook::fetch_recipes()}
+///     Loading cool recipes...
+/// {:await recipes}
+///     {recipes.len()} recipes found.
+/// {:catch err}
+///     Could not fetch recipes: {err}
+/// {/async}
+/// ```
+///
+/// With a plain future ([`AsyncHead::Future`]), `children` are shown whilst it is pending.
+/// With the `{#async let output = fut.await}` shorthand ([`AsyncHead::Let`]),
+/// `children` are only shown once the future is ready, so there is no `{:await ...}` divider.
+///
+pub struct Async {
+    pub brace: syn::token::Brace,
+    pub pound: Token![#],
+    pub async_token: Token![async],
+    pub head: AsyncHead,
+    pub children: Children,
+    pub then: Option<Await>,
+    pub catch: Option<Catch>,
+    pub closing: BlockClosing<Token![async]>,
+}
+
+impl syn::parse::Parse for Async {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Async {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        let pound = inner.parse()?;
+        let async_token = inner.parse()?;
+        let head = AsyncHead::parse(&inner, cx)?;
+        let children = parse_block_children(input, cx, brace)?;
+
+        let mut then: Option<Await> = None;
+        let mut catch: Option<Catch> = None;
+
+        loop {
+            match peek_block(input) {
+                Some((':', ref k)) if k == "await" => {
+                    if let AsyncHead::Let { .. } = head {
+                        return Err(Diagnostic::at(
+                            input,
+                            Code::MisplacedBranch,
+                            "`{:await ...}` cannot be used with the `{#async let ... = ....await}` shorthand",
+                        )
+                        .into());
+                    }
+
+                    if then.is_some() || catch.is_some() {
+                        return Err(Diagnostic::at(
+                            input,
+                            Code::MisplacedBranch,
+                            "`{:await ...}` must come once, before any `{:catch ...}`",
+                        )
+                        .into());
+                    }
+
+                    then = Some(Await::parse(input, cx)?);
+                }
+                Some((':', ref k)) if k == "catch" => {
+                    if catch.is_some() {
+                        return Err(Diagnostic::at(input, Code::MisplacedBranch, "`{#async}` blocks can only have one `{:catch ...}`").into());
+                    }
+
+                    catch = Some(Catch::parse(input, cx)?);
+                }
+                _ => {
+                    expect_closing(input, &brace, "async")?;
+                    break;
+                }
+            }
+        }
+
+        Ok(Self {
+            brace,
+            pound,
+            async_token,
+            head,
+            children,
+            then,
+            catch,
+            closing: input.parse()?,
+        })
+    }
+}
+
+impl Debug for Async {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("Async");
+
+        s.field("head", &self.head)
+            .field("children", &self.children);
+
+        if let Some(ref then) = self.then {
+            s.field("await", then);
+        }
+
+        if let Some(ref catch) = self.catch {
+            s.field("catch", catch);
+        }
+
+        s.finish()
+    }
+}
+
+///
+/// What an [`Async`] block is waiting on.
+///
+pub enum AsyncHead {
+    /// `{#async fut}`
+    Future(syn::Expr),
+
+    /// `{#async let output = fut.await}`
+    Let {
+        let_token: Token![let],
+        pat: syn::Pat,
+        eq: Token![=],
+        future: syn::ExprAwait,
+    },
+}
+
+impl parser::Parse for AsyncHead {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        if !input.peek(Token![let]) {
+            return Ok(Self::Future(input.parse()?));
+        }
+
+        let let_token = input.parse()?;
+        let pat = syn::Pat::parse_multi_with_leading_vert(input)?;
+        let eq = input.parse()?;
+
+        match input.parse()? {
+            syn::Expr::Await(future) => Ok(Self::Let {
+                let_token,
+                pat,
+                eq,
+                future,
+            }),
+            expr => Err(Diagnostic::new(
+                Code::ExpectedAwait,
+                expr.span(),
+                "Expected `.await` at the end of `{#async let ... = ...}`",
+            )
+            .suggest(expr.span(), "Await it", format!("{}.await", expr.to_token_stream()))
+            .into()),
+        }
+    }
+}
+
+impl Debug for AsyncHead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Future(expr) => write!(f, "{}", expr.to_token_stream()),
+            Self::Let { pat, future, .. } => write!(
+                f,
+                "let {} = {}",
+                pat.to_token_stream(),
+                future.to_token_stream()
+            ),
+        }
+    }
+}
+
+///
+/// `{:await output}` divider of an [`Async`] block,
+/// the binding can be omitted if the output is not needed.
+///
+pub struct Await {
+    pub brace: syn::token::Brace,
+    pub colon: Token![:],
+    pub await_token: Token![await],
+    pub pat: Option<syn::Pat>,
+    pub children: Children,
+}
+
+impl parser::Parse for Await {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        Ok(Self {
+            brace,
+            colon: inner.parse()?,
+            await_token: inner.parse()?,
+            pat: if inner.is_empty() {
+                None
+            } else {
+                Some(syn::Pat::parse_multi_with_leading_vert(&inner)?)
+            },
+            children: parse_block_children(input, cx, brace)?,
+        })
+    }
+}
+
+impl Debug for Await {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("Await");
+
+        if let Some(ref pat) = self.pat {
+            s.field("pat", &pat.to_token_stream().to_string());
+        }
+
+        s.field("children", &self.children).finish()
+    }
+}
+
+///
+/// `{:catch err}` arm of an [`Async`] block, for futures yielding a `Result`.
+///
+pub struct Catch {
+    pub brace: syn::token::Brace,
+    pub colon: Token![:],
+    pub catch_token: kw::catch,
+    pub pat: syn::Pat,
+    pub children: Children,
+}
+
+impl parser::Parse for Catch {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        Ok(Self {
+            brace,
+            colon: inner.parse()?,
+            catch_token: inner.parse()?,
+            pat: syn::Pat::parse_multi_with_leading_vert(&inner)?,
+            children: parse_block_children(input, cx, brace)?,
+        })
+    }
+}
+
+impl Debug for Catch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Catch")
+            .field("pat", &self.pat.to_token_stream().to_string())
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+///
+/// ```svelte
+/// {#key month}
+///     Wake me up when {month} ends.
+/// {/key}
+/// ```
+///
+/// The whole block is destroyed and re-created whenever `expr` changes.
+///
+pub struct Key {
+    pub brace: syn::token::Brace,
+    pub pound: Token![#],
+    pub key_token: kw::key,
+    pub expr: syn::Expr,
+    pub children: Children,
+    pub closing: BlockClosing<kw::key>,
+}
+
+impl syn::parse::Parse for Key {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Key {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
+        let pound = inner.parse()?;
+        let key_token = inner.parse()?;
+        let expr = inner.parse()?;
+        let children = parse_block_children(input, cx, brace)?;
+
+        expect_closing(input, &brace, "key")?;
+
+        Ok(Self {
+            brace,
+            pound,
+            key_token,
+            expr,
+            children,
+            closing: input.parse()?,
+        })
+    }
+}
+
+impl Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Key")
+            .field("expr", &self.expr.to_token_stream().to_string())
+            .field("children", &self.children)
+            .finish()
+    }
+}
+
+///
+/// Parses any block beginning with `{#...}`.
+///
+pub(crate) fn parse_block(input: ParseStream, cx: &mut Context) -> Result<Child> {
+    match peek_block(input) {
+        Some(('#', k)) => match k.as_str() {
+            "if" => Ok(Child::If(If::parse(input, cx)?)),
+            "for" => Ok(Child::For(For::parse(input, cx)?)),
+            "match" => Ok(Child::Match(Match::parse(input, cx)?)),
+            "async" => Ok(Child::Async(Box::new(Async::parse(input, cx)?))),
+            "key" => Ok(Child::Key(Key::parse(input, cx)?)),
+            _ => Err(Diagnostic::at(input, Code::UnknownBlock, format!("Unknown logic block `{{#{k}}}`")).into()),
+        },
+        Some((sigil, k)) => Err(Diagnostic::at(
+            input,
+            Code::StrayBlockTag,
+            format!("Unexpected `{{{sigil}{k}}}` here: there is no open block it belongs to"),
+        )
+        .into()),
+        None => Err(Diagnostic::at(input, Code::UnknownBlock, "Expected a logic block here").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::jsx::{Child, Element};
+
+    use super::{Async, AsyncHead, Condition, For, If, Key, Match};
+
+    #[test]
+    fn parse_if() {
+        let b: If = syn::parse_str(r#"
+            {#if project.language != "Rust"}
+                Ferris says, "Rewrite it in Rust!"
+            {:else}
+                Ferris is proud of you.
+            {/if}
+        "#).expect("Valid parse");
+
+        assert!(matches!(b.condition, Condition::Expr(_)));
+        assert!(b.else_ifs.is_empty());
+        assert!(b.otherwise.is_some());
+
+        let b: If = syn::parse_str(r#"
+            {#if let Some(ref chicken) = self.fridge.get("chicken")}
+                I have {chicken.weight} kgs of chicken.
+            {:else if self.fridge.is_empty()}
+                <Empty />
+            {:else if self.fridge.len() > 2}
+                Plenty!
+            {/if}
+        "#).expect("Valid parse");
+
+        assert!(matches!(b.condition, Condition::Let(_)));
+        assert_eq!(b.else_ifs.len(), 2);
+        assert!(b.otherwise.is_none());
+    }
+
+    #[test]
+    fn parse_nested_if() {
+        let e: Element = syn::parse_str(r#"
+            <Card>
+                {#if a}
+                    {#if b}<Both />{:else}<OnlyA />{/if}
+                {/if}
+                {::std::mem::size_of::<u8>()}
+            </Card>
+        "#).expect("Valid parse");
+
+        let Element::Closed(e) = e else { panic!("Expected closed element") };
+        assert!(matches!(e.children[0], Child::If(_)));
+        assert!(matches!(e.children[1], Child::Mustache(_)));
+    }
+
+    #[test]
+    fn if_errors() {
+        let e = syn::parse_str::<If>(r#"{#if a} Unclosed"#).expect_err("Invalid parse");
+        assert!(e.to_string().contains("never closed"));
+
+        syn::parse_str::<If>(r#"{#if a} A {:else} B {:else} C {/if}"#).expect_err("Invalid parse");
+        syn::parse_str::<If>(r#"{#if a} A {:else} B {:else if b} C {/if}"#).expect_err("Invalid parse");
+        syn::parse_str::<If>(r#"{#if a} A {/for}"#).expect_err("Invalid parse");
+        syn::parse_str::<Element>(r#"<A>{#if a} A </A>"#).expect_err("Invalid parse");
+
+        let e = syn::parse_str::<Element>(r#"<A>{:else}</A>"#).expect_err("Invalid parse");
+        assert!(e.to_string().contains("no open block"));
+    }
+
+    #[test]
+    fn parse_for() {
+        let b: For = syn::parse_str(r#"
+            {#for animal in ark.animals.iter()
+                .filter(|a| a.is(Mammal))
+            }
+                <Card>
+                    <Image src={&animal.img} />
+                    <Title>{&animal.name}</Title>
+                </Card>
+            {/for}
+        "#).expect("Valid parse");
+
+        assert!(matches!(b.pat, syn::Pat::Ident(_)));
+        assert!(b.otherwise.is_none());
+
+        let b: For = syn::parse_str(r#"
+            {#for (fruit, count) in basket.iter()}
+                <Icon id={fruit} /> {fruit} count: {count} <Br />
+            {:else}
+                No results.
+            {/for}
+        "#).expect("Valid parse");
+
+        assert!(matches!(b.pat, syn::Pat::Tuple(_)));
+        assert!(b.otherwise.is_some());
+    }
+
+    #[test]
+    fn for_errors() {
+        syn::parse_str::<For>(r#"{#for x in xs} {x}"#).expect_err("Invalid parse");
+        syn::parse_str::<For>(r#"{#for x in xs} A {:else} B {:else} C {/for}"#).expect_err("Invalid parse");
+        syn::parse_str::<For>(r#"{#for x in xs} A {:else if y} B {/for}"#).expect_err("Invalid parse");
+        syn::parse_str::<For>(r#"{#for x in xs} A {/if}"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn parse_match() {
+        let b: Match = syn::parse_str(r#"
+            {#match user.role()}
+                <!-- Root first. -->
+                {:case Role::Root}
+                    <T>With great power comes great responsibility.</T>
+                {:case Role::Sudoer | Role::Admin}
+                    <T>You are now (effectively) running as root.</T>
+                {:case _ if user.is_guest()}
+                    <T>Who are you?</T>
+                {:case _}
+                    <T>{user.username} is not in the sudoers file.</T>
+                    <T>This incident will be reported.</T>
+            {/match}
+        "#).expect("Valid parse");
+
+        assert_eq!(b.comments.len(), 1);
+        assert_eq!(b.arms.len(), 4);
+        assert!(matches!(b.arms[1].pat, syn::Pat::Or(_)));
+        assert!(b.arms[2].guard.is_some());
+        assert!(b.arms[3].guard.is_none());
+    }
+
+    #[test]
+    fn match_range_patterns() {
+        let b: Match = syn::parse_str(r#"
+            {#match film}
+                {:case Film(title, age_rating @ ..=user.age(), Genre::Action)}
+                    <Title>{title}</Title>
+                {:case Film(_, 0..=12, ..) | Film(_, ..MAX, _)}
+                    Kids
+            {/match}
+        "#).expect("Valid parse");
+
+        let syn::Pat::TupleStruct(ref pat) = b.arms[0].pat else { panic!("Expected tuple struct pattern") };
+        let syn::Pat::Ident(ref binding) = pat.elems[1] else { panic!("Expected binding") };
+        let syn::Pat::Range(ref range) = *binding.subpat.as_ref().unwrap().1 else { panic!("Expected range") };
+        assert!(matches!(range.end.as_deref(), Some(syn::Expr::MethodCall(_))));
+    }
+
+    #[test]
+    fn match_errors() {
+        let e = syn::parse_str::<Match>(r#"
+            {#match x}
+                Not a comment!
+                {:case _} A
+            {/match}
+        "#).expect_err("Invalid parse");
+        assert!(e.to_string().contains("Only comments"));
+
+        syn::parse_str::<Match>(r#"{#match x} {:case _} A {:else} B {/match}"#).expect_err("Invalid parse");
+        syn::parse_str::<Match>(r#"{#match x} {:case _} A"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn parse_async() {
+        let b: Async = syn::parse_str(r#"
+            {#async Cookbook::fetch_recipes()}
+                Loading cool recipes...
+                <!-- TODO: Make cool animation. -->
+            {:await recipes}
+                {#for recipe in recipes.into_iter()
+                    .map(Recipe::deserialize)
+                    .filter_map(Result::ok)
+                }
+                    <Recipe recipe={recipe}/>
+                {/for}
+            {:catch err}
+                Oh no: {err}
+            {/async}
+        "#).expect("Valid parse");
+
+        assert!(matches!(b.head, AsyncHead::Future(_)));
+        assert!(b.then.as_ref().is_some_and(|then| then.pat.is_some()));
+        assert!(b.catch.is_some());
+
+        let b: Async = syn::parse_str(r#"
+            {#async stocks.finished()}
+                <Button on={|| panic!()}>Click me</Button>
+            {/async}
+        "#).expect("Valid parse");
+
+        assert!(b.then.is_none() && b.catch.is_none());
+
+        let b: Async = syn::parse_str(r#"
+            {#async let order = user.latest_order().await }
+                Your latest order for {order.qty} X {order.item} has been processed.
+            {:catch _}
+                Something went wrong.
+            {/async}
+        "#).expect("Valid parse");
+
+        assert!(matches!(b.head, AsyncHead::Let { .. }));
+        assert!(b.catch.is_some());
+    }
+
+    #[test]
+    fn async_errors() {
+        syn::parse_str::<Async>(r#"{#async let x = fut} {x} {/async}"#).expect_err("Invalid parse");
+        syn::parse_str::<Async>(r#"{#async let x = fut.await} A {:await y} B {/async}"#).expect_err("Invalid parse");
+        syn::parse_str::<Async>(r#"{#async fut} A {:catch e} B {:await y} C {/async}"#).expect_err("Invalid parse");
+        syn::parse_str::<Async>(r#"{#async fut} A {:await y} B"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn parse_key() {
+        let b: Key = syn::parse_str(r#"
+            {#key month}
+                <!-- Green Day would be proud! -->
+                Wake me up when {month} ends.
+            {/key}
+        "#).expect("Valid parse");
+
+        assert_eq!(b.children.len(), 4);
+
+        syn::parse_str::<Key>(r#"{#key month} A {:else} B {/key}"#).expect_err("Invalid parse");
+        syn::parse_str::<Key>(r#"{#key month} A {/if}"#).expect_err("Invalid parse");
+    }
+}
\ No newline at end of file
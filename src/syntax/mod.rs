@@ -1,3 +1,11 @@
 pub mod jsx;
+pub mod lexer;
+pub mod entities;
 pub mod mustache;
 pub mod formatting;
+pub mod logic;
+pub mod tags;
+pub mod recovery;
+pub mod diagnostics;
+pub mod document;
+pub mod script;pub mod parser;
@@ -1,9 +1,13 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, ops::Range, str::FromStr};
 
+use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
 use quote::ToTokens;
-use syn::Token;
+use syn::{parse::ParseStream, Token};
 
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::entities;
 use super::formatting::Formatting;
+use super::parser::{self, Context};
 
 pub struct Mustache {
     pub brace: syn::token::Brace,
@@ -12,13 +16,19 @@ pub struct Mustache {
 }
 
 impl syn::parse::Parse for Mustache {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let inner;
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Mustache {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let (brace, inner) = parser::braced(input)?;
         Ok(Self {
-            brace: syn::braced!(inner in input),
+            brace,
             expr: inner.parse()?,
             formatting: if inner.peek(Token![:]) {
-                Some(inner.parse()?)
+                Some(FormattingGroup::parse(&inner, cx)?)
             } else {
                 None
             },
@@ -45,11 +55,11 @@ pub struct FormattingGroup {
     pub formatting: Formatting,
 }
 
-impl syn::parse::Parse for FormattingGroup {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl parser::Parse for FormattingGroup {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
         Ok(Self {
             colon: input.parse()?,
-            formatting: input.parse()?,
+            formatting: Formatting::parse(input, cx)?,
         })
     }
 }
@@ -60,13 +70,427 @@ impl Debug for FormattingGroup {
     }
 }
 
+///
+/// String literal with mustaches interpolated into it, as in
+/// `a11y:alt="Picture of {&animal.species}"`.
+///
+/// Like [`std::fmt`], `{{` and `}}` escape literal braces.
+///
+pub struct InterpolatedStr {
+    pub lit: syn::LitStr,
+    pub segments: Vec<StrSegment>,
+}
+
+pub enum StrSegment {
+    Lit(syn::LitStr),
+    Mustache(Box<Mustache>),
+}
+
+impl syn::parse::Parse for InterpolatedStr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for InterpolatedStr {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        let lit: syn::LitStr = input.parse()?;
+
+        if !lit.suffix().is_empty() {
+            return Err(Diagnostic::new(Code::InvalidString, lit.span(), "Unexpected suffix on string literal").into());
+        }
+
+        let segments = split_segments(&lit)?;
+
+        Ok(Self { lit, segments })
+    }
+}
+
+impl Debug for InterpolatedStr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(&self.segments).finish()
+    }
+}
+
+impl Debug for StrSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lit(lit) => write!(f, "{:?}", lit.value()),
+            Self::Mustache(mustache) => mustache.fmt(f),
+        }
+    }
+}
+
+///
+/// Span of `bytes` of the literal's source (prefix and quotes included),
+/// or of the whole literal where that can't be had, such as inside a proc macro
+/// on stable, which has no subspans.
+///
+fn subspan(lit: &syn::LitStr, bytes: Range<usize>) -> Span {
+    let token = lit.token();
+    let source = token.to_string();
+    let expected = &source[bytes.clone()];
+
+    // The compiler takes byte offsets, but `proc_macro2`'s fallback counts chars instead.
+    let chars = source[..bytes.start].chars().count()..source[..bytes.end].chars().count();
+
+    [bytes, chars]
+        .into_iter()
+        .filter_map(|range| token.subspan(range))
+        .find(|span| span.source_text().is_none_or(|text| text == expected))
+        .unwrap_or_else(|| lit.span())
+}
+
+fn unescape(lit: &syn::LitStr, raw: bool, source: &str, span: Span) -> Result<String> {
+    if raw || !source.contains('\\') {
+        return Ok(source.to_string());
+    }
+
+    syn::parse_str::<syn::LitStr>(&format!("\"{source}\""))
+        .map(|l| l.value())
+        .map_err(|_| Diagnostic::new(Code::InvalidString, span, format!("Invalid escape in `{}`", lit.token())).into())
+}
+
+///
+/// Each character of the literal's value in `range` of its `source`, with the bytes it was
+/// written as: more than one for an escape such as `\"` or `\u{7B}`.
+///
+fn chars(source: &str, range: Range<usize>, raw: bool) -> Vec<(char, Range<usize>)> {
+    let mut chars = vec![];
+    let mut iter = source[range.clone()].char_indices().map(|(i, c)| (range.start + i, c)).peekable();
+
+    while let Some((i, c)) = iter.next() {
+        if c != '\\' || raw {
+            chars.push((c, i..i + c.len_utf8()));
+            continue;
+        }
+
+        let Some((_, escape)) = iter.next() else { break };
+        let value = match escape {
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            '0' => Some('\0'),
+            'x' => {
+                let digits: String = (0..2).filter_map(|_| iter.next()).map(|(_, c)| c).collect();
+                u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32)
+            }
+            'u' => {
+                let mut digits = String::new();
+                while let Some((_, c)) = iter.next_if(|(_, c)| *c != '}') {
+                    digits.push(c);
+                }
+                iter.next();
+                u32::from_str_radix(digits.trim_start_matches('{'), 16).ok().and_then(char::from_u32)
+            }
+            // Line continuation: the newline and any whitespace after it are left out.
+            '\n' => {
+                while iter.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                continue;
+            }
+            c => Some(c),
+        };
+
+        let end = iter.peek().map_or(range.end, |(j, _)| *j);
+        chars.push((value.unwrap_or(char::REPLACEMENT_CHARACTER), i..end));
+    }
+
+    chars
+}
+
+fn split_segments(lit: &syn::LitStr) -> Result<Vec<StrSegment>> {
+    let source = lit.token().to_string();
+
+    // Skip over `"` or `r#"`, to the contents.
+    let raw = source.starts_with('r');
+    let hashes = source[1..].len() - source[1..].trim_start_matches('#').len();
+    let (start, end) = if raw {
+        (hashes + 2, source.len() - hashes - 1)
+    } else {
+        (1, source.len() - 1)
+    };
+
+    let chars = chars(&source, start..end, raw);
+
+    let mut segments = vec![];
+    let mut lit_start = start;
+    let mut i = 0;
+
+    let push_lit = |segments: &mut Vec<StrSegment>, range: Range<usize>| -> Result<()> {
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        let text = &source[range.clone()];
+        let span = subspan(lit, range.clone());
+        let value = unescape(lit, raw, text, span)?
+            .replace("{{", "{")
+            .replace("}}", "}");
+
+        let value = entities::decode(&value).map_err(|e| {
+            // Entities have no escapes or braces in them, so they can be found in the source as is.
+            let span = text.find(&e.entity).map_or(span, |at| {
+                let at = range.start + at;
+                subspan(lit, at..at + e.entity.len())
+            });
+
+            Diagnostic::new(Code::InvalidEntity, span, e.message)
+                .suggest(span, "Escape the `&`", format!("&amp;{}", &e.entity[1..]))
+        })?;
+
+        segments.push(StrSegment::Lit(syn::LitStr::new(&value, span)));
+        Ok(())
+    };
+
+    while i < chars.len() {
+        let (c, ref bytes) = chars[i];
+
+        match c {
+            '{' | '}' if chars.get(i + 1).is_some_and(|(next, _)| *next == c) => i += 2,
+            '}' => {
+                let span = subspan(lit, bytes.clone());
+                return Err(Diagnostic::new(Code::InvalidString, span, "Unmatched `}` in string, use `}}` to escape it")
+                    .suggest(span, "Escape it", "}}")
+                    .into());
+            }
+            '{' => {
+                push_lit(&mut segments, lit_start..bytes.start)?;
+
+                let Some(close) = mustache_end(&chars, i) else {
+                    let span = subspan(lit, bytes.clone());
+                    return Err(Diagnostic::new(Code::InvalidString, span, "Unclosed `{` in string, use `{{` to escape it")
+                        .suggest(span, "Escape it", "{{")
+                        .into());
+                };
+
+                let inner = bytes.end..chars[close].1.start;
+                segments.push(StrSegment::Mustache(Box::new(parse_mustache(lit, raw, &source, inner)?)));
+
+                i = close + 1;
+                lit_start = chars[close].1.end;
+            }
+            _ => i += 1,
+        }
+    }
+
+    push_lit(&mut segments, lit_start..end)?;
+
+    Ok(segments)
+}
+
+///
+/// Index of the `}` closing the mustache opened at `open`, skipping over
+/// any string and char literals inside it, which may have braces of their own.
+///
+fn mustache_end(chars: &[(char, Range<usize>)], open: usize) -> Option<usize> {
+    let at = |i: usize| chars.get(i).map(|(c, _)| *c);
+    let is_ident = |i: Option<usize>| i.and_then(at).is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    let mut depth = 0;
+    let mut i = open;
+
+    while i < chars.len() {
+        match chars[i].0 {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            '"' => {
+                i += 1;
+                while at(i)? != '"' {
+                    i += if at(i)? == '\\' { 2 } else { 1 };
+                }
+            }
+            // `r"..."` and `r#"..."#`, but not the end of an identifier like `for`.
+            'r' if !is_ident(i.checked_sub(1)) || (at(i - 1) == Some('b') && !is_ident(i.checked_sub(2))) => {
+                let hashes = (i + 1..).take_while(|j| at(*j) == Some('#')).count();
+
+                if at(i + 1 + hashes) == Some('"') {
+                    i += hashes + 2;
+                    while !(at(i)? == '"' && (1..=hashes).all(|n| at(i + n) == Some('#'))) {
+                        i += 1;
+                    }
+                    i += hashes;
+                }
+            }
+            // `'{'` and `'\''`, but not lifetimes like `'a`.
+            '\'' if at(i + 1) == Some('\\') => {
+                i += 3;
+                while at(i)? != '\'' {
+                    i += 1;
+                }
+            }
+            '\'' if at(i + 2) == Some('\'') => i += 2,
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+///
+/// Parses the mustache whose contents are the `inner` bytes of the literal's `source`.
+///
+fn parse_mustache(lit: &syn::LitStr, raw: bool, source: &str, inner: Range<usize>) -> Result<Mustache> {
+    let whole = subspan(lit, inner.start - 1..inner.end + 1);
+    let (start, inner) = (inner.start, &source[inner]);
+    let unescaped = unescape(lit, raw, inner, whole)?;
+
+    let tokens = TokenStream::from_str(&unescaped)
+        .map_err(|e| Diagnostic::new(Code::InvalidString, whole, format!("Invalid mustache in string: {e}")))?;
+
+    // Without escapes, every token can point at exactly where it is in the literal.
+    let tokens = if unescaped == inner {
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(inner.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        // Lines are counted from 1, and columns in chars.
+        let offset = |at: proc_macro2::LineColumn| {
+            let line = line_starts[at.line - 1];
+            line + inner[line..].chars().take(at.column).map(char::len_utf8).sum::<usize>()
+        };
+
+        respan(tokens, &|span| subspan(lit, start + offset(span.start())..start + offset(span.end())))
+    } else {
+        respan(tokens, &|_| whole)
+    };
+
+    let mut group = Group::new(Delimiter::Brace, tokens);
+    group.set_span(whole);
+
+    parser::parse2(group.into_token_stream())
+}
+
+fn respan(tokens: TokenStream, f: &impl Fn(Span) -> Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), respan(g.stream(), f));
+                group.set_span(f(g.span()));
+                TokenTree::Group(group)
+            }
+            mut tt => {
+                tt.set_span(f(tt.span()));
+                tt
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Mustache;
+    use quote::ToTokens;
+    use syn::spanned::Spanned;
+
+    use crate::syntax::parser;
+
+    use super::{InterpolatedStr, Mustache, StrSegment};
 
     #[test]
     fn mustache_parse() {
         let m: Mustache = syn::parse_str(r"{apple:'2'>?}").unwrap();
         println!("{m:?}")
     }
+
+    #[test]
+    fn interpolated_str() {
+        let s: InterpolatedStr = syn::parse_str(r#""Picture of {&animal.species}""#).expect("Valid parse");
+
+        assert!(matches!(s.segments[..], [StrSegment::Lit(ref l), StrSegment::Mustache(_)] if l.value() == "Picture of "));
+
+        let s: InterpolatedStr = syn::parse_str(r#""{count:'0'>3} {{items}}, {a}{b}""#).expect("Valid parse");
+        let [StrSegment::Mustache(ref count), StrSegment::Lit(ref l), StrSegment::Mustache(_), StrSegment::Mustache(_)] = s.segments[..] else {
+            panic!("Expected mustache, literal, mustache, mustache: {s:?}");
+        };
+        assert!(count.formatting.is_some());
+        assert_eq!(l.value(), " {items}, ");
+
+        let s: InterpolatedStr = syn::parse_str(r#""Crab: \u{1F980} \"{name}\"""#).expect("Valid parse");
+        assert_eq!(s.segments.len(), 3);
+
+        let s: InterpolatedStr = syn::parse_str(r##"r#"Raw "{x}""#"##).expect("Valid parse");
+        assert_eq!(s.segments.len(), 3);
+
+        let s: InterpolatedStr = syn::parse_str(r#""No mustaches""#).expect("Valid parse");
+        assert!(matches!(s.segments[..], [StrSegment::Lit(ref l)] if l.value() == "No mustaches"));
+
+        syn::parse_str::<InterpolatedStr>(r#""Unclosed {x""#).expect_err("Invalid parse");
+        syn::parse_str::<InterpolatedStr>(r#""Unmatched } here""#).expect_err("Invalid parse");
+        syn::parse_str::<InterpolatedStr>(r#""Bad {1 +}""#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn interpolated_str_spans() {
+        let s: InterpolatedStr = syn::parse_str(r#""Picture of {&animal.species}""#).expect("Valid parse");
+        let StrSegment::Mustache(ref m) = s.segments[1] else { panic!("Expected mustache") };
+
+        assert_eq!(m.brace.span.open().start().column, 12);
+        assert_eq!(m.expr.span().start().column, 13);
+        assert_eq!(m.expr.span().end().column, 28);
+
+        let e = parser::parse_str::<InterpolatedStr>(r#""a } b""#).expect_err("Invalid parse");
+        assert_eq!(e[0].span.start().column, 3);
+    }
+
+    #[test]
+    fn interpolated_str_entities() {
+        let s: InterpolatedStr = syn::parse_str(r#""Tom &amp; {name} &mdash; &#x1F980;""#).expect("Valid parse");
+        assert!(matches!(s.segments[..], [StrSegment::Lit(ref a), StrSegment::Mustache(_), StrSegment::Lit(ref b)]
+            if a.value() == "Tom & " && b.value() == " \u{2014} \u{1F980}"));
+
+        // The raw form is still there.
+        assert_eq!(s.lit.value(), "Tom &amp; {name} &mdash; &#x1F980;");
+
+        let e = parser::parse_str::<InterpolatedStr>(r#""{a} &rock;""#).expect_err("Invalid parse");
+        assert_eq!((e[0].span.start().column, e[0].span.end().column), (5, 11));
+    }
+
+    #[test]
+    fn interpolated_str_unicode() {
+        let s: InterpolatedStr = syn::parse_str(r#""Crème brûlée: {dessert.name} \u{1F370} {price}€""#).expect("Valid parse");
+        let [_, StrSegment::Mustache(ref name), _, StrSegment::Mustache(ref price), StrSegment::Lit(ref euro)] = s.segments[..] else {
+            panic!("Expected two mustaches: {s:?}");
+        };
+
+        assert_eq!(name.expr.span().source_text().as_deref(), Some("dessert.name"));
+        assert_eq!((name.expr.span().start().column, name.expr.span().end().column), (16, 28));
+        assert_eq!(price.expr.span().source_text().as_deref(), Some("price"));
+        assert_eq!(euro.value(), "€");
+
+        let e = parser::parse_str::<InterpolatedStr>(r#""Déjà } vu""#).expect_err("Invalid parse");
+        assert_eq!(e[0].span.source_text().as_deref(), Some("}"));
+    }
+
+    #[test]
+    fn interpolated_str_nested_literals() {
+        let s: InterpolatedStr = syn::parse_str(r#""{greet(\"}\")} and {'{'} or {'\\''}, {x.len()}""#).expect("Valid parse");
+        let mustaches: Vec<_> = s.segments
+            .iter()
+            .filter_map(|s| match s {
+                StrSegment::Mustache(m) => Some(m.expr.to_token_stream().to_string()),
+                StrSegment::Lit(_) => None,
+            })
+            .collect();
+
+        assert_eq!(mustaches, [r#"greet ("}")"#, "'{'", r"'\''", "x . len ()"]);
+
+        let s: InterpolatedStr = syn::parse_str(r##"r#"{format!("{}}", x)} {y}"#"##).expect("Valid parse");
+        assert_eq!(s.segments.len(), 3);
+
+        // Lifetimes aren't char literals.
+        let s: InterpolatedStr = syn::parse_str(r#""{f::<'a>()}""#).expect("Valid parse");
+        assert_eq!(s.segments.len(), 1);
+
+        // An escaped `{` is still a brace, as it is to `format!`.
+        let s: InterpolatedStr = syn::parse_str(r#""\x7Bx\x7D""#).expect("Valid parse");
+        assert!(matches!(s.segments[..], [StrSegment::Mustache(_)]));
+    }
 }
\ No newline at end of file
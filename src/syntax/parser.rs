@@ -0,0 +1,159 @@
+//!
+//! Parsing with an explicit [`Context`], returning [`Diagnostics`] on errors.
+//!
+//! Children are where errors are recovered from (see [`recovery`](super::recovery)),
+//! so everything is parsed with [`Parse`], which is given the context rather than
+//! looking it up anywhere. The [`syn::parse::Parse`] impls use a fresh, strict one,
+//! and turn the diagnostics into a [`syn::Error`].
+//!
+
+use proc_macro2::TokenStream;
+use syn::parse::{ParseBuffer, ParseStream, Parser};
+
+use super::diagnostics::{Code, Diagnostic, Diagnostics, Result};
+use super::jsx::ElementName;
+
+///
+/// How to parse, and what has been found so far.
+///
+#[derive(Default)]
+pub struct Context {
+    recovering: bool,
+    errors: Diagnostics,
+    ///
+    /// Names of the elements currently open, innermost last, whilst recovering.
+    ///
+    open: Vec<String>,
+}
+
+impl Context {
+    ///
+    /// A context that reports errors and carries on, instead of stopping at the first.
+    ///
+    pub fn recovering() -> Self {
+        Self {
+            recovering: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_recovering(&self) -> bool {
+        self.recovering
+    }
+
+    pub(crate) fn report(&mut self, e: Diagnostics) {
+        for d in e {
+            // The same tokens may be parsed more than once, from forks.
+            let key = |d: &Diagnostic| (d.span.start(), d.span.end(), d.message.clone());
+            if !self.errors.iter().any(|other| key(other) == key(&d)) {
+                self.errors.push(d);
+            }
+        }
+    }
+
+    ///
+    /// Reports `e` and carries on if recovering, otherwise gives it straight back.
+    ///
+    pub(crate) fn recover(&mut self, e: impl Into<Diagnostics>) -> Result<()> {
+        match self.recovering {
+            true => {
+                self.report(e.into());
+                Ok(())
+            }
+            false => Err(e.into()),
+        }
+    }
+
+    ///
+    /// Every error reported, if any.
+    ///
+    pub fn into_errors(self) -> Option<Diagnostics> {
+        Some(self.errors).filter(|errors| !errors.is_empty())
+    }
+
+    ///
+    /// Runs `f` with the element `name` marked as open, whilst recovering.
+    ///
+    pub(crate) fn within<T>(&mut self, name: &ElementName, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if !self.recovering {
+            return f(self);
+        }
+
+        self.open.push(name.to_string());
+        let result = f(self);
+        self.open.pop();
+
+        result
+    }
+
+    ///
+    /// Whether an element enclosing the innermost open one has this `name`.
+    ///
+    pub(crate) fn encloses(&self, name: &ElementName) -> bool {
+        let name = name.to_string();
+        self.open.iter().rev().skip(1).any(|open| *open == name)
+    }
+}
+
+///
+/// Same as [`syn::parse::Parse`], given a [`Context`].
+///
+pub trait Parse: Sized {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self>;
+}
+
+///
+/// Parses a `T`, stopping at the first error.
+///
+pub(crate) fn strict<T: Parse>(input: ParseStream) -> syn::Result<T> {
+    T::parse(input, &mut Context::default()).map_err(Into::into)
+}
+
+///
+/// Same as [`syn::braced!`], for use where errors are [`Diagnostics`].
+///
+pub(crate) fn braced<'a>(input: ParseStream<'a>) -> syn::Result<(syn::token::Brace, ParseBuffer<'a>)> {
+    let content;
+    let brace = syn::braced!(content in input);
+    Ok((brace, content))
+}
+
+///
+/// Same as [`syn::parse_str`].
+///
+pub fn parse_str<T: Parse>(source: &str) -> Result<T> {
+    parse2(syn::parse_str(source)?)
+}
+
+///
+/// Same as [`syn::parse2`].
+///
+pub fn parse2<T: Parse>(tokens: TokenStream) -> Result<T> {
+    parse_all(tokens, |input| T::parse(input, &mut Context::default()))
+}
+
+///
+/// Parses all of `tokens` with `f`, as with [`Parser::parse2`].
+///
+/// Errors from `f` come first, since syn also complains about anything
+/// it left unparsed, such as the rest of a `{...}` it gave up on.
+///
+pub(crate) fn parse_all<T>(tokens: TokenStream, f: impl FnOnce(ParseStream) -> Result<T>) -> Result<T> {
+    let mut value = None;
+    let parser = |input: ParseStream| {
+        value = Some(f(input).and_then(|value| match input.is_empty() {
+            true => Ok(value),
+            false => Err(Diagnostic::at(input, Code::Syntax, "unexpected token").into()),
+        }));
+
+        let _: TokenStream = input.parse()?;
+        Ok(())
+    };
+
+    let parsed = parser.parse2(tokens);
+    match value {
+        Some(Ok(value)) => parsed.map(|()| value).map_err(Into::into),
+        Some(Err(e)) => Err(e),
+        None => Err(parsed.expect_err("Nothing was parsed").into()),
+    }
+}
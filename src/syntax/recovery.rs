@@ -0,0 +1,368 @@
+//!
+//! Error recovery: parse a whole file, reporting every error rather than just the first.
+//!
+//! Whilst recovering, a child that fails to parse is replaced by a [`ErrorNode`],
+//! and parsing picks up again at the next tag, block, mustache or run of text.
+//! Elements and fragments that are never closed (or closed by the wrong tag)
+//! are closed where they should have been.
+//!
+//! Recovery only happens when asked for, through the functions here,
+//! which parse with a recovering [`Context`].
+//! Normal parsing (with [`syn::parse::Parse`]) still stops at the first error.
+//!
+
+use std::fmt::Debug;
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::TokenStreamExt;
+use syn::{buffer::Cursor, parse::ParseStream, parse::Parser, Token};
+
+use super::diagnostics::{Code, Diagnostic, Diagnostics, Result};
+use super::document::Document;
+use super::jsx::Root;
+use super::lexer;
+use super::logic;
+use super::parser::{Context, Parse};
+
+///
+/// Placeholder for a child that could not be parsed.
+///
+pub struct ErrorNode {
+    pub error: Diagnostics,
+    ///
+    /// Everything skipped over to get to the next child.
+    ///
+    pub tokens: TokenStream,
+}
+
+impl Debug for ErrorNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Error").field(&self.error.to_string()).finish()
+    }
+}
+
+///
+/// A partial [`Root`], with all the errors found on the way.
+///
+pub struct Recovered<T> {
+    ///
+    /// `None` if even the root couldn't be parsed.
+    ///
+    pub value: Option<T>,
+    ///
+    /// Every error, in order.
+    ///
+    pub errors: Option<Diagnostics>,
+}
+
+impl<T> Recovered<T> {
+    ///
+    /// Every error, or none.
+    ///
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.errors.as_deref().unwrap_or_default()
+    }
+
+    ///
+    /// The value, only if there were no errors at all.
+    ///
+    pub fn into_result(self) -> Result<T> {
+        match (self.value, self.errors) {
+            (Some(value), None) => Ok(value),
+            (_, Some(errors)) => Err(errors),
+            (None, None) => Err(Diagnostic::new(Code::Syntax, proc_macro2::Span::call_site(), "Nothing was parsed").into()),
+        }
+    }
+}
+
+///
+/// Parses a [`Root`] from `.pony` source, recovering from errors.
+///
+pub fn parse_str(source: &str) -> Recovered<Root> {
+    lex_then(source)
+}
+
+///
+/// Parses a [`Root`] from tokens, recovering from errors.
+///
+pub fn parse2(tokens: TokenStream) -> Recovered<Root> {
+    parse_with(tokens)
+}
+
+///
+/// Parses a whole component file from `.pony` source, recovering from errors.
+///
+pub fn parse_document(source: &str) -> Recovered<Document> {
+    lex_then(source)
+}
+
+fn lex_then<T: Parse>(source: &str) -> Recovered<T> {
+    match lexer::lex(source) {
+        Ok(tokens) => parse_with(tokens),
+        Err(e) => Recovered {
+            value: None,
+            errors: Some(e),
+        },
+    }
+}
+
+fn parse_with<T: Parse>(tokens: TokenStream) -> Recovered<T> {
+    let mut cx = Context::recovering();
+
+    let parser = |input: ParseStream| {
+        let root = T::parse(input, &mut cx);
+
+        if root.is_ok() && !input.is_empty() {
+            cx.report(Diagnostic::at(input, Code::UnexpectedToken, "Unexpected tokens after the root element").into());
+        }
+
+        let _: TokenStream = input.parse()?;
+        Ok(root)
+    };
+
+    let value = match parser.parse2(tokens) {
+        Ok(Ok(root)) => Some(root),
+        Ok(Err(e)) => {
+            cx.report(e);
+            None
+        }
+        Err(e) => {
+            cx.report(e.into());
+            None
+        }
+    };
+
+    Recovered {
+        value,
+        errors: cx.into_errors(),
+    }
+}
+
+///
+/// Reports `e`, then skips over the element or block that failed to parse
+/// (so its insides aren't taken for its siblings), or failing that,
+/// at least one token, up until a tag, block, mustache or run of text could start.
+///
+pub(crate) fn skip(input: ParseStream, cx: &mut Context, e: Diagnostics) -> Result<ErrorNode> {
+    cx.report(e.clone());
+
+    let end = element_end(input.cursor()).or_else(|| block_end(input.cursor()));
+    let mut tokens = TokenStream::new();
+
+    loop {
+        tokens.append(input.parse::<TokenTree>()?);
+
+        let done = match end {
+            Some(end) => input.cursor() == end,
+            None => {
+                input.peek(Token![<])
+                    || input.peek(syn::token::Brace)
+                    || input.cursor().token_tree().is_some_and(|(tt, _)| lexer::raw_text(&tt.into()).is_some())
+            }
+        };
+
+        if done || input.is_empty() {
+            break;
+        }
+    }
+
+    Ok(ErrorNode { error: e, tokens })
+}
+
+enum TagKind {
+    Opening,
+    Closing,
+    SelfClosing,
+}
+
+///
+/// The tag starting at `cursor`, with the first identifier of its name,
+/// and where it ends.
+///
+fn tag_at(cursor: Cursor) -> Option<(TagKind, String, Cursor)> {
+    let (lt, mut rest) = cursor.punct()?;
+    if lt.as_char() != '<' {
+        return None;
+    }
+
+    let closing = rest.punct().filter(|(p, _)| p.as_char() == '/');
+    if let Some((_, next)) = closing {
+        rest = next;
+    }
+
+    let (name, _) = rest.ident()?;
+
+    // Counting `<` and `>` also gets over generics and elements as attribute values.
+    let mut depth = 1;
+    let mut slash = false;
+
+    while let Some((tt, next)) = rest.token_tree() {
+        rest = next;
+
+        match tt {
+            TokenTree::Punct(ref p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(ref p) if p.as_char() == '>' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    let kind = match (closing.is_some(), slash) {
+                        (true, _) => TagKind::Closing,
+                        (false, true) => TagKind::SelfClosing,
+                        (false, false) => TagKind::Opening,
+                    };
+
+                    return Some((kind, name.to_string(), rest));
+                }
+            }
+            _ => {}
+        }
+
+        slash = matches!(tt, TokenTree::Punct(ref p) if p.as_char() == '/');
+    }
+
+    None
+}
+
+///
+/// Just after the closing tag of the element starting at `cursor`.
+///
+fn element_end(cursor: Cursor) -> Option<Cursor> {
+    let (kind, name, mut rest) = tag_at(cursor)?;
+
+    match kind {
+        TagKind::SelfClosing => return Some(rest),
+        TagKind::Closing => return None,
+        TagKind::Opening => {}
+    }
+
+    let mut depth = 1;
+
+    while !rest.eof() {
+        match tag_at(rest) {
+            Some((kind, other, next)) => {
+                match kind {
+                    TagKind::Opening if other == name => depth += 1,
+                    TagKind::Closing if other == name => depth -= 1,
+                    _ => {}
+                }
+
+                if depth == 0 {
+                    return Some(next);
+                }
+
+                rest = next;
+            }
+            None => rest = rest.token_tree()?.1,
+        }
+    }
+
+    None
+}
+
+///
+/// Just after the `{/...}` closing the block starting at `cursor`.
+///
+fn block_end(cursor: Cursor) -> Option<Cursor> {
+    let Some(('#', keyword)) = logic::block_at(cursor) else {
+        return None;
+    };
+
+    let mut depth = 0;
+    let mut rest = cursor;
+
+    while let Some((_, next)) = rest.token_tree() {
+        match logic::block_at(rest) {
+            Some(('#', ref k)) if *k == keyword => depth += 1,
+            Some(('/', ref k)) if *k == keyword => depth -= 1,
+            _ => {}
+        }
+
+        rest = next;
+
+        if depth == 0 {
+            return Some(rest);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::jsx::{Child, Element, Root};
+
+    use super::{parse_str, Recovered};
+
+    fn children(root: &Root) -> &[Child] {
+        let Root::Element(Element::Closed(ref e)) = root else { panic!("Expected closed element") };
+        &e.children
+    }
+
+    #[test]
+    fn no_errors() {
+        let r = parse_str("<Card>\n    <Title>Fine</Title>\n</Card>");
+        assert!(r.errors.is_none());
+        r.into_result().expect("Valid parse");
+
+        let r: Recovered<Root> = Recovered { value: None, errors: None };
+        r.into_result().expect_err("Nothing parsed");
+    }
+
+    #[test]
+    fn all_errors() {
+        let r = parse_str(r#"<Card>
+            <Title size=>Oops</Title>
+            {#if}Nope{/if}
+            <Body>{}</Body>
+            <Footer>Fine</Footer>
+        </Card>"#);
+
+        let errors = r.errors.expect("Invalid parse");
+        assert_eq!(errors.into_iter().count(), 3);
+
+        let root = r.value.expect("Partial parse");
+        let c = children(&root);
+        assert!(matches!(c, [Child::Error(_), Child::Error(_), Child::Element(_), Child::Element(_)]));
+
+        let Child::Element(Element::Closed(ref body)) = c[2] else { panic!("Expected closed element") };
+        assert!(matches!(body.children[..], [Child::Error(_)]));
+    }
+
+    #[test]
+    fn unclosed() {
+        let r = parse_str("<Card>\n    <Title>Oops\n");
+        assert_eq!(r.errors.expect("Invalid parse").into_iter().count(), 2);
+
+        let root = r.value.expect("Partial parse");
+        let Child::Element(Element::Closed(ref title)) = children(&root)[0] else { panic!("Expected closed element") };
+        assert_eq!(title.children.len(), 1);
+
+        let r = parse_str("<Card><Title>Oops</Card>");
+        assert_eq!(r.errors.expect("Invalid parse").into_iter().count(), 1);
+        assert!(matches!(children(&r.value.expect("Partial parse"))[0], Child::Element(_)));
+
+        let r = parse_str("<>Oops");
+        assert!(r.errors.is_some() && matches!(r.value, Some(Root::Fragment(_))));
+    }
+
+    #[test]
+    fn stray_closing() {
+        let r = parse_str("<Card>Oops</Title> fine</Card>");
+        assert_eq!(r.errors.expect("Invalid parse").into_iter().count(), 1);
+
+        let root = r.value.expect("Partial parse");
+        assert!(matches!(children(&root), [Child::Text(_), Child::Error(_), Child::Text(_)]));
+    }
+
+    #[test]
+    fn unrecoverable() {
+        let r = parse_str("Nothing to see here");
+        assert!(r.value.is_none() && r.errors.is_some());
+
+        let r = parse_str("<Card></Card> <Extra />");
+        assert!(r.value.is_some() && r.errors.is_some());
+
+        // Outside of recovery, the first error is still the only error.
+        crate::syntax::lexer::parse_str::<Root>("<Card><Title>Oops</Card>").expect_err("Invalid parse");
+    }
+}
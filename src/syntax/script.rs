@@ -0,0 +1,368 @@
+//!
+//! The Rust inside `<script>`: ordinary items, plus `extern` prop declarations
+//! and top-level `let`s for private state, which aren't valid Rust on their own.
+//!
+//! ```rust,ignore
+//! /// Player's remaining health points.
+//! extern let health: i32;
+//!
+//! #[derive(Default)]
+//! extern {
+//!     let mut basket: HashMap<String, usize> = Default::default();
+//! }
+//!
+//! let mut selected: Option<String> = None;
+//! ```
+//!
+
+use std::fmt::Debug;
+
+use quote::ToTokens;
+use syn::{parse::ParseStream, Token};
+
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::parser::{self, Context};
+
+pub struct ScriptBlock {
+    ///
+    /// `#![...]` and `//!` at the very top.
+    ///
+    pub attrs: Vec<syn::Attribute>,
+    pub items: Vec<ScriptItem>,
+}
+
+impl syn::parse::Parse for ScriptBlock {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for ScriptBlock {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let attrs = syn::Attribute::parse_inner(input)?;
+        let mut items = vec![];
+
+        // Rust items never start with `</`, so this stops at `</script>`.
+        while !(input.is_empty() || (input.peek(Token![<]) && input.peek2(Token![/]))) {
+            items.push(ScriptItem::parse(input, cx)?);
+        }
+
+        Ok(Self { attrs, items })
+    }
+}
+
+impl Debug for ScriptBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(&self.items).finish()
+    }
+}
+
+pub enum ScriptItem {
+    ///
+    /// `extern let name: Type = default;`
+    ///
+    Prop(Box<ExternLet>),
+    ///
+    /// `extern { let name: Type = default; ... }`
+    ///
+    Props(ExternBlock),
+    ///
+    /// `let name: Type = init;`, private state.
+    ///
+    State(Box<PropLet>),
+    ///
+    /// Anything else: functions, `use`, `struct`s, ...
+    ///
+    Item(Box<syn::Item>),
+}
+
+impl parser::Parse for ScriptItem {
+    fn parse(input: ParseStream, cx: &mut Context) -> Result<Self> {
+        let f = input.fork();
+        let _ = syn::Attribute::parse_outer(&f)?;
+
+        if f.peek(Token![extern]) && f.peek2(Token![let]) {
+            return Ok(Self::Prop(Box::new(ExternLet::parse(input, cx)?)));
+        }
+
+        if f.peek(Token![extern]) && f.peek2(syn::token::Brace) {
+            return Ok(Self::Props(ExternBlock::parse(input, cx)?));
+        }
+
+        if f.peek(Token![let]) {
+            let attrs = syn::Attribute::parse_outer(input)?;
+            return Ok(Self::State(Box::new(PropLet::parse_after(input, attrs, "State")?)));
+        }
+
+        Ok(Self::Item(Box::new(input.parse()?)))
+    }
+}
+
+impl Debug for ScriptItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Prop(prop) => prop.fmt(f),
+            Self::Props(props) => props.fmt(f),
+            Self::State(state) => f.debug_tuple("State").field(state).finish(),
+            Self::Item(item) => write!(f, "Item({:?})", item.to_token_stream().to_string()),
+        }
+    }
+}
+
+///
+/// `extern let name: Type = default;`
+///
+/// Outer attributes (including `///` docs) belong to the prop.
+///
+pub struct ExternLet {
+    pub extern_token: Token![extern],
+    pub prop: PropLet,
+}
+
+impl parser::Parse for ExternLet {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        let attrs = syn::Attribute::parse_outer(input)?;
+        let extern_token = input.parse()?;
+        let prop = PropLet::parse_after(input, attrs, "Prop")?;
+
+        Ok(Self { extern_token, prop })
+    }
+}
+
+impl Debug for ExternLet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Prop").field(&self.prop).finish()
+    }
+}
+
+///
+/// `extern { let a: A; let b: B = b; }`
+///
+/// Outer attributes (including `///` docs) belong to the group,
+/// and each prop may have its own.
+///
+pub struct ExternBlock {
+    pub attrs: Vec<syn::Attribute>,
+    pub extern_token: Token![extern],
+    pub brace: syn::token::Brace,
+    pub props: Vec<PropLet>,
+}
+
+impl parser::Parse for ExternBlock {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        let attrs = syn::Attribute::parse_outer(input)?;
+        let extern_token = input.parse()?;
+
+        let (brace, inner) = parser::braced(input)?;
+        let mut props = vec![];
+
+        while !inner.is_empty() {
+            let attrs = syn::Attribute::parse_outer(&inner)?;
+
+            if !inner.peek(Token![let]) {
+                return Err(Diagnostic::at(&inner, Code::InvalidProp, "Expected `let` here: `extern` blocks only declare props")
+                    .note("Other items go outside of the `extern` block")
+                    .into());
+            }
+
+            props.push(PropLet::parse_after(&inner, attrs, "Prop")?);
+        }
+
+        Ok(Self {
+            attrs,
+            extern_token,
+            brace,
+            props,
+        })
+    }
+}
+
+impl Debug for ExternBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Props")
+            .field("attrs", &self.attrs.iter().map(|attr| attr.to_token_stream().to_string()).collect::<Vec<_>>())
+            .field("props", &self.props)
+            .finish()
+    }
+}
+
+///
+/// `let mut name: Type = default;`, either declaring a prop (after `extern`),
+/// or private state (on its own, where `default` is its initial value).
+///
+pub struct PropLet {
+    pub attrs: Vec<syn::Attribute>,
+    pub let_token: Token![let],
+    pub mutability: Option<Token![mut]>,
+    pub ident: syn::Ident,
+    pub colon: Token![:],
+    pub ty: syn::Type,
+    pub default: Option<(Token![=], syn::Expr)>,
+    pub semi: Token![;],
+}
+
+impl parser::Parse for PropLet {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        let attrs = syn::Attribute::parse_outer(input)?;
+        Self::parse_after(input, attrs, "Prop")
+    }
+}
+
+impl PropLet {
+    ///
+    /// Same as parsing a [`PropLet`], with its outer `attrs` already parsed.
+    /// `what` names what is declared, `"Prop"` or `"State"`, for errors.
+    ///
+    fn parse_after(input: ParseStream, attrs: Vec<syn::Attribute>, what: &str) -> Result<Self> {
+        let let_token = input.parse()?;
+        let mutability = input.parse()?;
+
+        if !input.peek(syn::Ident) {
+            return Err(Diagnostic::at(input, Code::InvalidProp, format!("Expected the {}'s name here", what.to_lowercase()))
+                .note(format!("{what} is declared one name at a time, without patterns"))
+                .into());
+        }
+
+        let ident: syn::Ident = input.parse()?;
+
+        if !input.peek(Token![:]) {
+            return Err(Diagnostic::new(Code::InvalidProp, ident.span(), format!("{what} `{ident}` needs a type"))
+                .note(format!("Type inference is not supported for {}", what.to_lowercase()))
+                .suggest(ident.span(), "Give it a type", format!("{ident}: Type"))
+                .into());
+        }
+
+        Ok(Self {
+            attrs,
+            let_token,
+            mutability,
+            ident,
+            colon: input.parse()?,
+            ty: input.parse()?,
+            default: if input.peek(Token![=]) {
+                Some((input.parse()?, input.parse()?))
+            } else {
+                None
+            },
+            semi: input.parse()?,
+        })
+    }
+}
+
+impl Debug for PropLet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("Let");
+
+        s.field("mut", &self.mutability.is_some())
+            .field("name", &self.ident.to_string())
+            .field("ty", &self.ty.to_token_stream().to_string());
+
+        if let Some((_, ref default)) = self.default {
+            s.field("default", &default.to_token_stream().to_string());
+        }
+
+        s.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::diagnostics::Code;
+    use crate::syntax::parser;
+
+    use super::{ScriptBlock, ScriptItem};
+
+    #[test]
+    fn parse_script() {
+        let s: ScriptBlock = syn::parse_str(r#"
+            use my_cool::library::Item;
+
+            /// Player's remaining health points.
+            extern let health: i32;
+            extern let mut score: i32 = 0;
+
+            ///
+            /// Plugin from the official plugin repository.
+            ///
+            #[derive(Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            extern {
+                let id: String;
+
+                ///
+                /// They'll never see it comin'...
+                ///
+                #[serde(default = "https://www.youtube.com/watch?v=dQw4w9WgXcQ")]
+                let homepage: String;
+
+                let selected_item: Option<&Item> = Default::default();
+            }
+
+            pub fn add(fruit: impl ToString) {}
+            extern "C" fn callback() {}
+            extern crate alloc;
+        "#).expect("Valid parse");
+
+        assert_eq!(s.items.len(), 7);
+        assert!(matches!(s.items[0], ScriptItem::Item(ref item) if matches!(**item, syn::Item::Use(_))));
+
+        let ScriptItem::Prop(ref health) = s.items[1] else { panic!("Expected prop") };
+        assert_eq!(health.prop.ident, "health");
+        assert!(health.prop.mutability.is_none() && health.prop.default.is_none());
+        assert!(health.prop.attrs[0].path().is_ident("doc"));
+
+        let ScriptItem::Prop(ref score) = s.items[2] else { panic!("Expected prop") };
+        assert!(score.prop.mutability.is_some() && score.prop.default.is_some());
+
+        let ScriptItem::Props(ref plugin) = s.items[3] else { panic!("Expected props") };
+        assert_eq!(plugin.attrs.len(), 5);
+        assert_eq!(plugin.props.len(), 3);
+        assert_eq!(plugin.props[1].attrs.len(), 4);
+        assert!(plugin.props[2].default.is_some());
+
+        assert!(matches!(s.items[4], ScriptItem::Item(ref item) if matches!(**item, syn::Item::Fn(_))));
+        assert!(matches!(s.items[5], ScriptItem::Item(ref item) if matches!(**item, syn::Item::Fn(_))));
+        assert!(matches!(s.items[6], ScriptItem::Item(ref item) if matches!(**item, syn::Item::ExternCrate(_))));
+
+        let s: ScriptBlock = syn::parse_str("#![allow(unused)]\n//! Inner docs").expect("Valid parse");
+        assert!(s.attrs.len() == 2 && s.items.is_empty());
+    }
+
+    #[test]
+    fn invalid_props() {
+        let e = parser::parse_str::<ScriptBlock>("extern let score = 0;").expect_err("Invalid parse");
+        let d = &e[0];
+        assert_eq!((d.code, d.message.as_str()), (Code::InvalidProp, "Prop `score` needs a type"));
+        assert_eq!(d.suggestions[0].replacement, "score: Type");
+
+        let e = parser::parse_str::<ScriptBlock>("extern { fn f() {} }").expect_err("Invalid parse");
+        assert_eq!(e[0].code, Code::InvalidProp);
+
+        let e = parser::parse_str::<ScriptBlock>("extern let (a, b): (i32, i32);").expect_err("Invalid parse");
+        assert_eq!(e[0].code, Code::InvalidProp);
+
+        syn::parse_str::<ScriptBlock>("extern let score: i32").expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn parse_state() {
+        let s: ScriptBlock = syn::parse_str(r#"
+            extern let mut fruits: Vec<String>;
+
+            /// Private to the component.
+            let mut basket: FruitBasket = Default::default();
+            let visits: usize;
+        "#).expect("Valid parse");
+
+        let ScriptItem::State(ref basket) = s.items[1] else { panic!("Expected state") };
+        assert_eq!(basket.ident, "basket");
+        assert!(basket.mutability.is_some() && basket.default.is_some());
+        assert!(basket.attrs[0].path().is_ident("doc"));
+
+        let ScriptItem::State(ref visits) = s.items[2] else { panic!("Expected state") };
+        assert!(visits.mutability.is_none() && visits.default.is_none());
+
+        let e = parser::parse_str::<ScriptBlock>("let count = 0;").expect_err("Invalid parse");
+        assert_eq!(e[0].message, "State `count` needs a type");
+    }
+}
@@ -0,0 +1,211 @@
+//!
+//! Tags: special one-liner directives in markup, of the form `{@ ...}`.
+//!
+
+use std::fmt::Debug;
+
+use quote::ToTokens;
+use syn::{parse::ParseStream, punctuated::Punctuated, Token};
+
+use super::diagnostics::{Code, Diagnostic, Result};
+use super::parser::{self, Context};
+
+pub mod kw {
+    syn::custom_keyword!(debug);
+}
+
+pub enum Tag {
+    Let(Box<LetTag>),
+    Debug(DebugTag),
+    Macro(MacroTag),
+}
+
+impl syn::parse::Parse for Tag {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        parser::strict(input)
+    }
+}
+
+impl parser::Parse for Tag {
+    fn parse(input: ParseStream, _: &mut Context) -> Result<Self> {
+        let f = input.fork();
+        let (_, inner) = parser::braced(&f)?;
+        let _: Token![@] = inner.parse()?;
+
+        if inner.peek(Token![let]) {
+            return Ok(Self::Let(Box::new(input.parse()?)));
+        }
+
+        // `{@debug!(...)}` is still a macro.
+        if inner.peek(kw::debug) && !inner.peek2(Token![!]) {
+            return Ok(Self::Debug(input.parse()?));
+        }
+
+        if inner.peek(syn::Ident) || inner.peek(Token![::]) {
+            return Ok(Self::Macro(input.parse()?));
+        }
+
+        Err(Diagnostic::at(&inner, Code::InvalidTag, "Expected `{@let ...}`, `{@debug ...}` or `{@macro!(...)}` here").into())
+    }
+}
+
+impl Debug for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Let(tag) => tag.fmt(f),
+            Self::Debug(tag) => tag.fmt(f),
+            Self::Macro(tag) => tag.fmt(f),
+        }
+    }
+}
+
+///
+/// `{@let words: &str = "Let me entertain you!"}`
+///
+pub struct LetTag {
+    pub brace: syn::token::Brace,
+    pub at: Token![@],
+    pub let_token: Token![let],
+    pub pat: syn::Pat,
+    pub ty: Option<(Token![:], syn::Type)>,
+    pub eq: Token![=],
+    pub init: syn::Expr,
+}
+
+impl syn::parse::Parse for LetTag {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let inner;
+        Ok(Self {
+            brace: syn::braced!(inner in input),
+            at: inner.parse()?,
+            let_token: inner.parse()?,
+            pat: syn::Pat::parse_single(&inner)?,
+            ty: if inner.peek(Token![:]) {
+                Some((inner.parse()?, inner.parse()?))
+            } else {
+                None
+            },
+            eq: inner.parse()?,
+            init: inner.parse()?,
+        })
+    }
+}
+
+impl Debug for LetTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("Let");
+
+        s.field("pat", &self.pat.to_token_stream().to_string());
+
+        if let Some((_, ref ty)) = self.ty {
+            s.field("ty", &ty.to_token_stream().to_string());
+        }
+
+        s.field("init", &self.init.to_token_stream().to_string())
+            .finish()
+    }
+}
+
+///
+/// `{@debug a, b}`, reserved for a breakpoint-based debugger.
+///
+pub struct DebugTag {
+    pub brace: syn::token::Brace,
+    pub at: Token![@],
+    pub debug_token: kw::debug,
+    pub exprs: Punctuated<syn::Expr, Token![,]>,
+}
+
+impl syn::parse::Parse for DebugTag {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let inner;
+        Ok(Self {
+            brace: syn::braced!(inner in input),
+            at: inner.parse()?,
+            debug_token: inner.parse()?,
+            exprs: Punctuated::parse_terminated(&inner)?,
+        })
+    }
+}
+
+impl Debug for DebugTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Debug")
+            .field(&self.exprs.to_token_stream().to_string())
+            .finish()
+    }
+}
+
+///
+/// `{@println!("ISBN-10: {}", book.isbn_10())}`
+///
+pub struct MacroTag {
+    pub brace: syn::token::Brace,
+    pub at: Token![@],
+    pub mac: syn::Macro,
+}
+
+impl syn::parse::Parse for MacroTag {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let inner;
+        Ok(Self {
+            brace: syn::braced!(inner in input),
+            at: inner.parse()?,
+            mac: inner.parse()?,
+        })
+    }
+}
+
+impl Debug for MacroTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Macro")
+            .field(&self.mac.to_token_stream().to_string())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::jsx::{Child, Element};
+
+    use super::Tag;
+
+    #[test]
+    fn parse_tags() {
+        let t: Tag = syn::parse_str(r#"{@let words = "Let me entertain you!"}"#).expect("Valid parse");
+        assert!(matches!(t, Tag::Let(ref tag) if tag.ty.is_none()));
+
+        let t: Tag = syn::parse_str(r#"{@let price : f64 = 420.6969}"#).expect("Valid parse");
+        assert!(matches!(t, Tag::Let(ref tag) if tag.ty.is_some()));
+
+        let t: Tag = syn::parse_str(r#"{@let (a, b) = pair}"#).expect("Valid parse");
+        assert!(matches!(t, Tag::Let(_)));
+
+        let t: Tag = syn::parse_str(r#"{@debug book, shelf.len()}"#).expect("Valid parse");
+        assert!(matches!(t, Tag::Debug(ref tag) if tag.exprs.len() == 2));
+
+        let t: Tag = syn::parse_str(r#"{@println!("ISBN-10: {}", book.isbn_10())}"#).expect("Valid parse");
+        assert!(matches!(t, Tag::Macro(_)));
+
+        let t: Tag = syn::parse_str(r#"{@debug!("Not the debugger")}"#).expect("Valid parse");
+        assert!(matches!(t, Tag::Macro(_)));
+
+        syn::parse_str::<Tag>(r#"{@let = 5}"#).expect_err("Invalid parse");
+        syn::parse_str::<Tag>(r#"{@println("Not a macro")}"#).expect_err("Invalid parse");
+        syn::parse_str::<Tag>(r#"{@5}"#).expect_err("Invalid parse");
+    }
+
+    #[test]
+    fn tags_in_markup() {
+        let e: Element = syn::parse_str(r#"
+            <Lyrics>
+                {@let words = "Let me entertain you!"}
+                {words}
+            </Lyrics>
+        "#).expect("Valid parse");
+
+        let Element::Closed(e) = e else { panic!("Expected closed element") };
+        assert!(matches!(e.children[0], Child::Tag(_)));
+        assert!(matches!(e.children[1], Child::Mustache(_)));
+    }
+}
\ No newline at end of file